@@ -0,0 +1,77 @@
+use blstrs::Scalar as Fr;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use generic_array::typenum;
+use neptune::matrix::{mat_mul, mat_mul_array, Matrix};
+use neptune::mds::mds_default;
+use neptune::poseidon::{apply_round_matrix, HashMode, PoseidonConstants};
+use neptune::*;
+use typenum::{U11, U2, U4, U8};
+
+fn bench_permute<A>(c: &mut Criterion)
+where
+    A: Arity<Fr>,
+{
+    let arity = A::to_usize();
+    let constants = PoseidonConstants::<Fr, A>::new();
+    // A fixed input, so runs are comparable.
+    let preimage: Vec<Fr> = (0..arity).map(|i| Fr::from(i as u64 + 1)).collect();
+    let state: Vec<Fr> = (0..constants.width()).map(|i| Fr::from(i as u64)).collect();
+
+    let mut group = c.benchmark_group(format!("permute-arity-{}", arity));
+
+    group.bench_function("optimized", |b| {
+        let mut h = Poseidon::<Fr, A>::new_with_preimage(&preimage, &constants);
+        b.iter(|| {
+            h.set_preimage(&preimage);
+            h.hash_in_mode(HashMode::OptimizedStatic)
+        })
+    });
+
+    group.bench_function("correct", |b| {
+        let mut h = Poseidon::<Fr, A>::new_with_preimage(&preimage, &constants);
+        b.iter(|| {
+            h.set_preimage(&preimage);
+            h.hash_in_mode(HashMode::Correct)
+        })
+    });
+
+    // Round 0 is a full round, which uses the dense MDS matrix.
+    group.bench_function("mds", |b| {
+        b.iter(|| apply_round_matrix(black_box(&state), 0, &constants))
+    });
+
+    // The round after the first half of full rounds is partial, and uses a sparse matrix.
+    let partial_round = constants.full_rounds() / 2;
+    group.bench_function("sparse", |b| {
+        b.iter(|| apply_round_matrix(black_box(&state), partial_round, &constants))
+    });
+
+    group.finish();
+}
+
+// `N` is the width, i.e. the MDS matrix size, of arity `N - 1`.
+fn bench_mat_mul<const N: usize>(c: &mut Criterion) {
+    let m: Matrix<Fr> = mds_default(N);
+    let array: [[Fr; N]; N] = core::array::from_fn(|i| core::array::from_fn(|j| m[i][j]));
+
+    let mut group = c.benchmark_group(format!("mat-mul-arity-{}", N - 1));
+
+    group.bench_function("vec", |b| b.iter(|| mat_mul(black_box(&m), black_box(&m))));
+
+    group.bench_function("array", |b| {
+        b.iter(|| mat_mul_array(black_box(&array), black_box(&array)))
+    });
+
+    group.finish();
+}
+
+criterion_group! {
+    name = permute;
+
+    config = Criterion::default();
+
+    targets = bench_permute::<U2>, bench_permute::<U4>, bench_permute::<U8>, bench_permute::<U11>,
+        bench_mat_mul::<3>, bench_mat_mul::<5>, bench_mat_mul::<9>, bench_mat_mul::<12>
+}
+
+criterion_main!(permute);
@@ -0,0 +1,45 @@
+//! Uses `neptune` without its `std` feature from a `#![no_std]` crate. CI builds this crate for
+//! `thumbv7em-none-eabi`, which has no `std`, so the build fails if anything in the dependency graph links it. The
+//! tests run the same functions on the host.
+#![no_std]
+
+extern crate alloc;
+
+#[cfg(test)]
+extern crate std;
+
+use generic_array::typenum::U2;
+use neptune::matrix::{invert_checked, mat_mul, Matrix};
+use neptune::poseidon::{Poseidon, PoseidonConstants};
+use pasta_curves::Fp;
+
+/// `m · m⁻¹`, which is the identity for any invertible `m`.
+pub fn times_inverse(m: &Matrix<Fp>) -> Option<Matrix<Fp>> {
+    mat_mul(m, &invert_checked(m)?)
+}
+
+/// Hash two elements with the default arity-2 constants.
+pub fn hash2(a: Fp, b: Fp) -> Fp {
+    let constants = PoseidonConstants::<Fp, U2>::new();
+    Poseidon::<Fp, U2>::new_with_preimage(&[a, b], &constants).hash()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use neptune::matrix::make_identity;
+    use neptune::mds::mds_default;
+
+    #[test]
+    fn times_inverse_is_identity() {
+        let m = mds_default::<Fp>(3);
+        assert_eq!(Some(make_identity(3)), times_inverse(&m));
+    }
+
+    #[test]
+    fn hash2_is_deterministic() {
+        let digest = hash2(Fp::from(1), Fp::from(2));
+        assert_eq!(digest, hash2(Fp::from(1), Fp::from(2)));
+        assert_ne!(digest, hash2(Fp::from(2), Fp::from(1)));
+    }
+}
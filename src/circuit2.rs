@@ -160,6 +160,10 @@ where
 {
     /// Create a new Poseidon hasher for `preimage`.
     pub fn new(elements: Vec<Elt<Scalar>>, constants: &'a PoseidonConstants<Scalar, A>) -> Self {
+        assert_eq!(
+            constants.sbox_degree, 5,
+            "circuits only support the quintic S-box"
+        );
         let width = constants.width();
 
         PoseidonCircuit2 {
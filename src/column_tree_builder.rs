@@ -174,6 +174,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_column_tree_builder_incremental() {
+        let leaves = 64;
+        let columns: Vec<GenericArray<Fr, U11>> = (0..leaves)
+            .map(|i| GenericArray::generate(|j| Fr::from((i * 11 + j) as u64)))
+            .collect();
+
+        let mut single_shot = ColumnTreeBuilder::<Fr, U11, U8>::new(None, None, leaves).unwrap();
+        let expected = single_shot.add_final_columns(&columns).unwrap();
+
+        let mut incremental = ColumnTreeBuilder::<Fr, U11, U8>::new(None, None, leaves).unwrap();
+        for batch in columns[..48].chunks(16) {
+            incremental.add_columns(batch).unwrap();
+        }
+        let (base, tree) = incremental.add_final_columns(&columns[48..]).unwrap();
+
+        assert_eq!(expected, (base.clone(), tree));
+        for (column, leaf) in columns.iter().zip(&base) {
+            assert_eq!(
+                Poseidon::new_with_preimage(column, &incremental.column_constants).hash(),
+                *leaf
+            );
+        }
+
+        // The builder is reset after the final columns, so it can be reused.
+        assert_eq!(expected, incremental.add_final_columns(&columns).unwrap());
+    }
+
     fn test_column_tree_builder_aux(
         column_batcher: Option<Batcher<Fr, U11>>,
         tree_batcher: Option<Batcher<Fr, U8>>,
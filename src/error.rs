@@ -1,4 +1,5 @@
-use std::{error, fmt};
+use alloc::string::String;
+use core::fmt;
 
 #[derive(Debug, Clone)]
 #[cfg(any(feature = "cuda", feature = "opencl"))]
@@ -15,7 +16,7 @@ pub enum ClError {
 }
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
-pub type ClResult<T> = std::result::Result<T, ClError>;
+pub type ClResult<T> = core::result::Result<T, ClError>;
 
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 impl fmt::Display for ClError {
@@ -47,12 +48,36 @@ pub enum Error {
     FullBuffer,
     /// Attempt to reference an index element that is out of bounds
     IndexOutOfBounds,
+    /// The preimage length does not match the length required by the constants.
+    InputLengthMismatch {
+        expected: usize,
+        got: usize,
+    },
+    /// Poseidon constants cannot be generated for the given arity.
+    UnsupportedArity(usize),
+    /// Poseidon constants cannot be generated for the given hash type.
+    UnsupportedHashType,
+    /// The S-box `x^d` of the given degree `d` is not supported or is not a permutation of the field.
+    UnsupportedSBoxDegree(u8),
+    /// The supplied matrix is not an MDS matrix of the required width.
+    NotMds,
     GpuError(String),
     #[cfg(any(feature = "cuda", feature = "opencl"))]
     ClError(ClError),
     Other(String),
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Possible error states for the checked matrix operations.
+pub enum MatrixError {
+    /// The matrix is not square, or its rows differ in length.
+    NotSquare,
+    /// The vector length does not match the size of the matrix.
+    SizeMismatch { expected: usize, got: usize },
+    /// An entry is not the canonical representation of a field element.
+    NonCanonical,
+}
+
 #[cfg(any(feature = "cuda", feature = "opencl"))]
 impl From<ec_gpu_gen::rust_gpu_tools::GPUError> for Error {
     fn from(e: ec_gpu_gen::rust_gpu_tools::GPUError) -> Self {
@@ -67,7 +92,11 @@ impl From<ec_gpu_gen::EcError> for Error {
     }
 }
 
-impl error::Error for Error {}
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl std::error::Error for MatrixError {}
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
@@ -77,6 +106,16 @@ impl fmt::Display for Error {
                 "The size of the buffer cannot be greater than the hash arity."
             ),
             Error::IndexOutOfBounds => write!(f, "The referenced index is outs of bounds."),
+            Error::InputLengthMismatch { expected, got } => write!(
+                f,
+                "Invalid preimage size: expected {expected} elements, got {got}."
+            ),
+            Error::UnsupportedArity(arity) => write!(f, "Unsupported arity: {arity}."),
+            Error::UnsupportedHashType => write!(f, "Unsupported hash type."),
+            Error::UnsupportedSBoxDegree(degree) => {
+                write!(f, "Unsupported S-box degree: {degree}.")
+            }
+            Error::NotMds => write!(f, "The matrix is not an MDS matrix of the required width."),
             Error::GpuError(s) => write!(f, "GPU Error: {s}"),
             #[cfg(any(feature = "cuda", feature = "opencl"))]
             Error::ClError(e) => write!(f, "OpenCL Error: {e}"),
@@ -84,3 +123,18 @@ impl fmt::Display for Error {
         }
     }
 }
+
+impl fmt::Display for MatrixError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        match self {
+            MatrixError::NotSquare => write!(f, "The matrix is not square."),
+            MatrixError::SizeMismatch { expected, got } => write!(
+                f,
+                "Invalid vector size: expected {expected} elements, got {got}."
+            ),
+            MatrixError::NonCanonical => {
+                write!(f, "Entry is not a canonical field element representation.")
+            }
+        }
+    }
+}
@@ -1,18 +1,23 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![allow(dead_code)]
 #![allow(unused_imports)]
 #[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+#[macro_use]
 extern crate lazy_static;
 
 pub use crate::poseidon::{Arity, Poseidon};
 use crate::round_constants::generate_constants;
 use crate::round_numbers::{round_numbers_base, round_numbers_strengthened};
+use alloc::vec::Vec;
 #[cfg(test)]
 use blstrs::Scalar as Fr;
-pub use error::Error;
+use core::fmt;
+pub use error::{Error, MatrixError};
 use ff::PrimeField;
 use generic_array::GenericArray;
 use serde::{Deserialize, Serialize};
-use std::fmt;
 use trait_set::trait_set;
 
 #[cfg(all(
@@ -42,20 +47,29 @@ compile_error!("The `strengthened` feature needs the `cuda` and/or `opencl` feat
 compile_error!("The `cuda` and `opencl` features need the `bls` and/or `pasta` feature to be set");
 
 /// Poseidon circuit
+#[cfg(feature = "std")]
 pub mod circuit;
+#[cfg(feature = "std")]
 pub mod circuit2;
 pub mod error;
-mod matrix;
-mod mds;
+/// Matrix operations over prime fields
+pub mod matrix;
+/// MDS matrices and their sparse factorization
+pub mod mds;
 
 /// Poseidon hash
 pub mod poseidon;
 mod poseidon_alt;
 mod preprocessing;
+/// The `Ring` trait, for matrix multiplication over arbitrary commutative rings
+pub mod ring;
 mod round_constants;
 mod round_numbers;
+#[cfg(test)]
+mod test_field;
 
 /// Sponge
+#[cfg(feature = "std")]
 pub mod sponge;
 
 /// Hash types and domain separation tags.
@@ -95,6 +109,12 @@ trait_set! {
 
 mod serde_impl;
 
+/// Hex string hashing for WebAssembly
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "serde_json")]
+pub use serde_impl::{constants_from_json, constants_to_json};
+
 pub(crate) const TEST_SEED: [u8; 16] = [
     0x59, 0x62, 0xbe, 0x5d, 0x76, 0x3d, 0x31, 0x8d, 0x17, 0xdb, 0x37, 0x32, 0x54, 0x06, 0xbc, 0xe5,
 ];
@@ -116,6 +136,9 @@ impl fmt::Display for Strength {
 
 pub(crate) const DEFAULT_STRENGTH: Strength = Strength::Standard;
 
+/// The S-box degree used unless another is requested, i.e. the quintic S-box `x^5`.
+pub const DEFAULT_SBOX_DEGREE: u8 = 5;
+
 pub trait BatchHasher<F, A>
 where
     F: PrimeField,
@@ -147,9 +170,18 @@ where
 }
 
 pub fn round_numbers(arity: usize, strength: &Strength) -> (usize, usize) {
+    round_numbers_with_sbox_degree(arity, strength, DEFAULT_SBOX_DEGREE)
+}
+
+/// Like `round_numbers`, but for an S-box of the given degree (either 3 or 5).
+pub fn round_numbers_with_sbox_degree(
+    arity: usize,
+    strength: &Strength,
+    sbox_degree: u8,
+) -> (usize, usize) {
     match strength {
-        Strength::Standard => round_numbers_base(arity),
-        Strength::Strengthened => round_numbers_strengthened(arity),
+        Strength::Standard => round_numbers_base(arity, sbox_degree),
+        Strength::Strengthened => round_numbers_strengthened(arity, sbox_degree),
     }
 }
 
@@ -165,20 +197,159 @@ pub(crate) fn scalar_from_u64s(parts: [u64; 4]) -> Fr {
     Fr::from_repr_vartime(repr).expect("u64s exceed BLS12-381 scalar field modulus")
 }
 
+/// Construct a field element from a `u64`.
+pub fn scalar_from_u64<F: PrimeField>(n: u64) -> F {
+    F::from(n)
+}
+
+/// Construct a vector of field elements, e.g. a hash state, from a slice of `u64`s.
+pub fn scalar_vec_from_u64s<F: PrimeField>(ns: &[u64]) -> Vec<F> {
+    ns.iter().copied().map(scalar_from_u64).collect()
+}
+
+/// Construct a field element from a `u128`, which may exceed the range of `F::from(u64)`.
+pub fn scalar_from_u128<F: PrimeField>(n: u128) -> F {
+    // 2^64
+    let mut shift = F::from(u64::MAX);
+    shift.add_assign(&F::one());
+
+    let mut res = F::from((n >> 64) as u64);
+    res.mul_assign(&shift);
+    res.add_assign(&F::from(n as u64));
+    res
+}
+
+/// Construct a field element from the little-endian bytes of its integer value. Returns `None` if the value is not
+/// less than the field modulus, rather than reducing it. `bytes` may be shorter than `(F::NUM_BITS + 7) / 8`, or
+/// longer if the extra bytes are zero.
+///
+/// This does not assume anything about the byte order of `F::Repr`: the value is accumulated arithmetically, then
+/// checked against `scalar_to_bytes_le`, which only round-trips for values below the modulus.
+pub fn scalar_from_bytes_le<F: PrimeField>(bytes: &[u8]) -> Option<F> {
+    let len = (F::NUM_BITS as usize + 7) / 8;
+    if bytes.iter().skip(len).any(|byte| *byte != 0) {
+        return None;
+    }
+    let bytes = &bytes[..bytes.len().min(len)];
+
+    let radix = F::from(256);
+    let x = bytes.iter().rev().fold(F::zero(), |acc, byte| {
+        acc * radix + F::from(u64::from(*byte))
+    });
+
+    let canonical = scalar_to_bytes_le(&x);
+    let (low, high) = canonical.split_at(bytes.len());
+    if low == bytes && high.iter().all(|byte| *byte == 0) {
+        Some(x)
+    } else {
+        None
+    }
+}
+
+/// The little-endian bytes of the integer value of `x`, `(F::NUM_BITS + 7) / 8` of them. Unlike `x.to_repr()`, whose
+/// byte order is up to the field implementation, the bits are read off arithmetically with `is_odd` and halving.
+pub fn scalar_to_bytes_le<F: PrimeField>(x: &F) -> Vec<u8> {
+    let num_bits = F::NUM_BITS as usize;
+    // The field has odd characteristic, so 2 is invertible.
+    let half = F::from(2).invert().unwrap();
+
+    let mut x = *x;
+    let mut bytes = vec![0u8; (num_bits + 7) / 8];
+    for i in 0..num_bits {
+        if bool::from(x.is_odd()) {
+            bytes[i / 8] |= 1 << (i % 8);
+            x -= F::one();
+        }
+        // `x` is even here, so multiplying by 1/2 halves its integer value.
+        x *= half;
+    }
+    bytes
+}
+
+/// Construct a field element from an `i64`. Negative values map to their additive inverse, i.e. `-n` maps to `0 - n`.
+pub fn scalar_from_i64<F: PrimeField>(n: i64) -> F {
+    let abs = F::from(n.unsigned_abs());
+    if n < 0 {
+        let mut res = F::zero();
+        res.sub_assign(&abs);
+        res
+    } else {
+        abs
+    }
+}
+
+/// A cheap, non-cryptographic digest of `x`: the 64-bit FNV-1a hash of its representation. It is stable across
+/// runs and platforms, so it can be used in test vectors as well as for hash map keys.
+pub fn scalar_digest<F: PrimeField>(x: &F) -> u64 {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    x.to_repr()
+        .as_ref()
+        .iter()
+        .fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// A field element usable as a `HashMap` key, hashed via `scalar_digest`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HashableScalar<F: PrimeField>(pub F);
+
+impl<F: PrimeField> core::hash::Hash for HashableScalar<F> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(scalar_digest(&self.0));
+    }
+}
+
+/// Compare two vectors of field elements in constant time (with respect to their values), by comparing the bytes
+/// of their representations. Vectors of different lengths are never equal.
+#[cfg(feature = "ct")]
+pub fn ct_eq_vec<F: PrimeField>(a: &[F], b: &[F]) -> subtle::Choice {
+    use subtle::ConstantTimeEq;
+
+    if a.len() != b.len() {
+        return subtle::Choice::from(0);
+    }
+    a.iter()
+        .zip(b)
+        .fold(subtle::Choice::from(1), |acc, (a, b)| {
+            acc & a.to_repr().as_ref().ct_eq(b.to_repr().as_ref())
+        })
+}
+
+/// Overwrite `elements` with zero, so the clearing of memory which is about to be freed is not optimized away. Field
+/// elements do not implement `Zeroize` themselves, so their memory is cleared through `zeroize`'s implementation for
+/// `[MaybeUninit<F>]`, which does the volatile writes, and then refilled with valid zero elements.
+#[cfg(feature = "zeroize")]
+pub(crate) fn zeroize_elements<F: PrimeField>(elements: &mut [F]) {
+    use core::mem::MaybeUninit;
+    use zeroize::Zeroize;
+
+    // SAFETY: `MaybeUninit<F>` has the same layout as `F` and is valid for any bytes. Every element is written with
+    // a valid `F` below, before `elements` can be read again.
+    let uninit = unsafe { &mut *(elements as *mut [F] as *mut [MaybeUninit<F>]) };
+    uninit.zeroize();
+    for elt in uninit.iter_mut() {
+        elt.write(F::zero());
+    }
+}
+
 const SBOX: u8 = 1; // x^5
 const FIELD: u8 = 1; // Gf(p)
 
-fn round_constants<F: PrimeField>(arity: usize, strength: &Strength) -> Vec<F> {
+fn round_constants<F: PrimeField>(arity: usize, strength: &Strength, sbox_degree: u8) -> Vec<F> {
     let t = arity + 1;
 
-    let (full_rounds, partial_rounds) = round_numbers(arity, strength);
+    let (full_rounds, partial_rounds) =
+        round_numbers_with_sbox_degree(arity, strength, sbox_degree);
 
     let r_f = full_rounds as u16;
     let r_p = partial_rounds as u16;
 
     let fr_num_bits = F::NUM_BITS;
     let field_size = {
-        assert!(fr_num_bits <= std::u16::MAX as u32);
+        assert!(fr_num_bits <= u16::MAX as u32);
         // It's safe to convert to u16 for compatibility with other types.
         fr_num_bits as u16
     };
@@ -186,6 +357,45 @@ fn round_constants<F: PrimeField>(arity: usize, strength: &Strength) -> Vec<F> {
     generate_constants::<F>(FIELD, SBOX, field_size, t as u16, r_f, r_p)
 }
 
+/// Apply the S-Box of the given degree (either 3 or 5) to a given item.
+pub(crate) fn s_box<F: PrimeField>(
+    sbox_degree: u8,
+    l: &mut F,
+    pre_add: Option<&F>,
+    post_add: Option<&F>,
+) {
+    match sbox_degree {
+        3 => cubic_s_box(l, pre_add, post_add),
+        5 => quintic_s_box(l, pre_add, post_add),
+        _ => unreachable!("S-box degrees are validated on construction"),
+    }
+}
+
+/// Apply the cubic S-Box (s^3) to a given item
+pub(crate) fn cubic_s_box<F: PrimeField>(l: &mut F, pre_add: Option<&F>, post_add: Option<&F>) {
+    if let Some(x) = pre_add {
+        l.add_assign(x);
+    }
+    let tmp = l.square(); // l^2
+    l.mul_assign(&tmp); // l^3
+    if let Some(x) = post_add {
+        l.add_assign(x);
+    }
+}
+
+/// Returns `true` if `x -> x^sbox_degree` is a permutation of `F`, i.e. if `sbox_degree` is 3 or 5 and does
+/// not divide `p - 1`.
+pub fn is_valid_sbox_degree<F: PrimeField>(sbox_degree: u8) -> bool {
+    if sbox_degree != 3 && sbox_degree != 5 {
+        return false;
+    }
+    let p_minus_one = scalar_to_bytes_le(&-F::one());
+    let rem = p_minus_one.iter().rev().fold(0u16, |rem, byte| {
+        (rem * 256 + *byte as u16) % sbox_degree as u16
+    });
+    rem != 0
+}
+
 /// Apply the quintic S-Box (s^5) to a given item
 pub(crate) fn quintic_s_box<F: PrimeField>(l: &mut F, pre_add: Option<&F>, post_add: Option<&F>) {
     if let Some(x) = pre_add {
@@ -200,9 +410,245 @@ pub(crate) fn quintic_s_box<F: PrimeField>(l: &mut F, pre_add: Option<&F>, post_
     }
 }
 
+/// The exponent `d = 5⁻¹ mod (p - 1)`, so that `(x⁵)ᵈ = x`. Since `d < p`, it is returned as the representation of
+/// the field element with value `d`. Panics if 5 divides `p - 1`, i.e. if the quintic S-box is not a permutation of
+/// the field.
+pub fn sbox_inverse_exponent<F: PrimeField>() -> F::Repr {
+    scalar_from_bytes_le::<F>(&sbox_inverse_exponent_le::<F>())
+        .expect("d is less than p")
+        .to_repr()
+}
+
+/// The little-endian bytes of `d = 5⁻¹ mod (p - 1)`.
+fn sbox_inverse_exponent_le<F: PrimeField>() -> Vec<u8> {
+    let p_minus_one = scalar_to_bytes_le(&-F::one());
+
+    // `d = (k(p - 1) + 1) / 5`, for the `k` in `1..5` making the division exact.
+    let rem = p_minus_one
+        .iter()
+        .rev()
+        .fold(0u16, |rem, byte| (rem * 256 + *byte as u16) % 5);
+    let k = (1..5u16)
+        .find(|k| (rem * k + 1) % 5 == 0)
+        .expect("5 divides p - 1, so the quintic S-box has no inverse");
+
+    let mut numerator = Vec::with_capacity(p_minus_one.len() + 1);
+    let mut carry = 1u16;
+    for byte in &p_minus_one {
+        let val = *byte as u16 * k + carry;
+        numerator.push(val as u8);
+        carry = val >> 8;
+    }
+    numerator.push(carry as u8);
+
+    let mut exponent = vec![0u8; p_minus_one.len()];
+    let mut rem = 0u16;
+    for (i, byte) in numerator.iter().enumerate().rev() {
+        let val = rem * 256 + *byte as u16;
+        rem = val % 5;
+        if i < exponent.len() {
+            exponent[i] = (val / 5) as u8;
+        } else {
+            assert_eq!(val / 5, 0);
+        }
+    }
+    assert_eq!(rem, 0);
+
+    exponent
+}
+
+/// Apply the inverse of the quintic S-Box (s^(1/5)) to a given item. This is variable time.
+pub fn inv_sbox<F: PrimeField>(x: &mut F) {
+    let limbs: Vec<u64> = sbox_inverse_exponent_le::<F>()
+        .chunks(8)
+        .map(|chunk| {
+            let mut bytes = [0u8; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            u64::from_le_bytes(bytes)
+        })
+        .collect();
+
+    *x = x.pow_vartime(&limbs);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ff::Field;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
+
+    #[test]
+    fn test_is_valid_sbox_degree() {
+        // 3 divides `p - 1` for both BLS12-381 and Pasta, and 5 divides neither.
+        test_is_valid_sbox_degree_aux::<Fr>(false);
+        test_is_valid_sbox_degree_aux::<pasta_curves::Fp>(false);
+    }
+
+    fn test_is_valid_sbox_degree_aux<F: PrimeField>(cubic: bool) {
+        assert_eq!(cubic, is_valid_sbox_degree::<F>(3));
+        assert!(is_valid_sbox_degree::<F>(5));
+        for degree in [0, 1, 2, 4, 7] {
+            assert!(!is_valid_sbox_degree::<F>(degree));
+        }
+    }
+
+    #[test]
+    fn test_inv_sbox() {
+        test_inv_sbox_aux::<Fr>();
+        test_inv_sbox_aux::<pasta_curves::Fp>();
+    }
+
+    fn test_inv_sbox_aux<F: PrimeField>() {
+        let exponent = F::from_repr(sbox_inverse_exponent::<F>()).unwrap();
+        assert_eq!(
+            sbox_inverse_exponent_le::<F>(),
+            scalar_to_bytes_le(&exponent)
+        );
+
+        let mut rng = XorShiftRng::from_seed(TEST_SEED);
+        let mut xs = vec![F::zero(), F::one(), -F::one(), F::from(5)];
+        xs.extend((0..10).map(|_| F::random(&mut rng)));
+
+        for x in xs {
+            let mut y = x;
+            quintic_s_box(&mut y, None, None);
+            inv_sbox(&mut y);
+            assert_eq!(x, y);
+
+            let mut z = x;
+            inv_sbox(&mut z);
+            quintic_s_box(&mut z, None, None);
+            assert_eq!(x, z);
+        }
+    }
+
+    #[test]
+    fn test_scalar_vec_from_u64s() {
+        let state = scalar_vec_from_u64s::<Fr>(&[1, 2, 3]);
+        assert_eq!(vec![Fr::one(), Fr::from(2), Fr::from(3)], state);
+        assert_eq!(Fr::from(u64::MAX), scalar_from_u64::<Fr>(u64::MAX));
+        assert!(scalar_vec_from_u64s::<Fr>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_scalar_from_u128() {
+        assert_eq!(Fr::from(7), scalar_from_u128::<Fr>(7));
+        assert_eq!(Fr::from(u64::MAX), scalar_from_u128::<Fr>(u64::MAX as u128));
+        assert_eq!(
+            scalar_from_u64s([0, 1, 0, 0]),
+            scalar_from_u128::<Fr>(u64::MAX as u128 + 1)
+        );
+        assert_eq!(
+            scalar_from_u64s([3, 5, 0, 0]),
+            scalar_from_u128::<Fr>((5u128 << 64) + 3)
+        );
+    }
+
+    #[test]
+    fn test_scalar_digest() {
+        // The digest must not change between runs or releases.
+        assert_eq!(0x96e2_b0a6_4c61_6a8f, scalar_digest(&Fr::from(42)));
+        assert_eq!(
+            scalar_digest(&Fr::from(7)),
+            scalar_digest(&(Fr::from(3) + Fr::from(4)))
+        );
+        assert_ne!(scalar_digest(&Fr::zero()), scalar_digest(&Fr::one()));
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(HashableScalar(Fr::from(5)), "five");
+        assert_eq!(
+            Some(&"five"),
+            map.get(&HashableScalar(Fr::from(2) + Fr::from(3)))
+        );
+        assert_eq!(None, map.get(&HashableScalar(Fr::from(6))));
+    }
+
+    #[test]
+    fn test_scalar_from_bytes_le() {
+        // The BLS12-381 scalar field modulus, little-endian.
+        let mut modulus = [0u8; 32];
+        modulus[..8].copy_from_slice(&0xffff_ffff_0000_0001u64.to_le_bytes());
+        modulus[8..16].copy_from_slice(&0x53bd_a402_fffe_5bfeu64.to_le_bytes());
+        modulus[16..24].copy_from_slice(&0x3339_d808_09a1_d805u64.to_le_bytes());
+        modulus[24..].copy_from_slice(&0x73ed_a753_299d_7d48u64.to_le_bytes());
+        assert_eq!(None, scalar_from_bytes_le::<Fr>(&modulus));
+        assert_eq!(None, scalar_from_bytes_le::<Fr>(&[0xff; 32]));
+
+        let mut modulus_minus_one = modulus;
+        modulus_minus_one[0] -= 1;
+        assert_eq!(
+            Some(Fr::zero() - Fr::one()),
+            scalar_from_bytes_le::<Fr>(&modulus_minus_one)
+        );
+
+        assert_eq!(Some(Fr::from(0x0102)), scalar_from_bytes_le::<Fr>(&[2, 1]));
+        assert_eq!(Some(Fr::zero()), scalar_from_bytes_le::<Fr>(&[]));
+        assert_eq!(
+            Some(Fr::from(7)),
+            scalar_from_bytes_le::<Fr>(&[&[7u8][..], &[0; 40]].concat())
+        );
+        let mut too_long = [0u8; 33];
+        too_long[32] = 1;
+        assert_eq!(None, scalar_from_bytes_le::<Fr>(&too_long));
+
+        test_scalar_bytes_le_aux::<Fr>();
+        test_scalar_bytes_le_aux::<pasta_curves::Fp>();
+    }
+
+    fn test_scalar_bytes_le_aux<F: PrimeField>() {
+        let len = (F::NUM_BITS as usize + 7) / 8;
+
+        let mut expected = vec![0u8; len];
+        expected[..3].copy_from_slice(&[3, 2, 1]);
+        assert_eq!(expected, scalar_to_bytes_le(&F::from(0x01_0203)));
+
+        let mut rng = XorShiftRng::from_seed(TEST_SEED);
+        let mut xs = vec![F::zero(), F::one(), -F::one()];
+        xs.extend((0..10).map(|_| F::random(&mut rng)));
+        for x in xs {
+            let bytes = scalar_to_bytes_le(&x);
+            assert_eq!(len, bytes.len());
+            assert_eq!(Some(x), scalar_from_bytes_le(&bytes));
+        }
+
+        // `p - 1` plus one, with the carry propagated, is the modulus.
+        let mut modulus = scalar_to_bytes_le(&-F::one());
+        for byte in modulus.iter_mut() {
+            let (sum, carry) = byte.overflowing_add(1);
+            *byte = sum;
+            if !carry {
+                break;
+            }
+        }
+        assert_eq!(None, scalar_from_bytes_le::<F>(&modulus));
+    }
+
+    #[test]
+    fn test_scalar_from_i64() {
+        assert_eq!(Fr::zero() - Fr::one(), scalar_from_i64::<Fr>(-1));
+        assert_eq!(Fr::from(42), scalar_from_i64::<Fr>(42));
+        assert_eq!(Fr::zero(), scalar_from_i64::<Fr>(0));
+
+        assert_eq!(
+            Fr::zero(),
+            scalar_from_i64::<Fr>(i64::MIN) + scalar_from_u128::<Fr>(1u128 << 63)
+        );
+    }
+
+    #[cfg(feature = "ct")]
+    #[test]
+    fn test_ct_eq_vec() {
+        let a = vec![Fr::from(1), Fr::from(2), Fr::from(3)];
+        let mut b = a.clone();
+
+        assert_eq!(1, ct_eq_vec(&a, &b).unwrap_u8());
+
+        b[2] = Fr::from(4);
+        assert_eq!(0, ct_eq_vec(&a, &b).unwrap_u8());
+        assert_eq!(0, ct_eq_vec(&a, &a[..2]).unwrap_u8());
+        assert_eq!(1, ct_eq_vec::<Fr>(&[], &[]).unwrap_u8());
+    }
 
     #[test]
     fn test_strengthened_round_numbers() {
@@ -225,7 +671,7 @@ mod tests {
         ];
 
         cases.iter().for_each(|(arity, expected_rounds)| {
-            let (full_rounds, actual_rounds) = round_numbers_strengthened(*arity);
+            let (full_rounds, actual_rounds) = round_numbers_strengthened(*arity, 5);
             assert_eq!(8, full_rounds);
             assert_eq!(
                 *expected_rounds, actual_rounds,
@@ -1,6 +1,14 @@
 // Allow `&Matrix` in function signatures.
 #![allow(clippy::ptr_arg)]
 
+// Only the module is imported: with `Ring` itself in scope, its `add_assign` and `mul_assign` would be ambiguous with
+// the operator traits' for `PrimeField` types.
+use crate::ring;
+use crate::{scalar_from_bytes_le, scalar_to_bytes_le, Error, MatrixError};
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::{AddAssign, Deref};
 use ff::PrimeField;
 
 /// Matrix functions here are, at least for now, quick and dirty — intended only to support precomputation of poseidon optimization.
@@ -8,6 +16,27 @@ use ff::PrimeField;
 /// Matrix represented as a Vec of rows, so that m[i][j] represents the jth column of the ith row in Matrix, m.
 pub type Matrix<T> = Vec<Vec<T>>;
 
+/// A `Matrix` whose `Debug` output gives its dimensions, then its entries as by `format_matrix`, rather than the
+/// field elements' verbose formatting. Derefs to the wrapped `Matrix`, so it can be passed to the functions here.
+#[derive(Clone, PartialEq)]
+pub struct Mat<F: PrimeField>(pub Matrix<F>);
+
+impl<F: PrimeField> Deref for Mat<F> {
+    type Target = Matrix<F>;
+
+    fn deref(&self) -> &Matrix<F> {
+        &self.0
+    }
+}
+
+impl<F: PrimeField> fmt::Debug for Mat<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cols = self.0.first().map_or(0, Vec::len);
+        writeln!(f, "{}x{} matrix", rows(&self.0), cols)?;
+        write!(f, "{}", format_matrix(&self.0))
+    }
+}
+
 pub fn rows<T>(matrix: &Matrix<T>) -> usize {
     matrix.len()
 }
@@ -28,10 +57,15 @@ fn columns<T>(matrix: &Matrix<T>) -> usize {
     }
 }
 
-// This wastefully discards the actual inverse, if it exists, so in general callers should
-// just call `invert` if that result will be needed.
+/// Returns `true` if every row of `matrix` has the same length.
+pub fn validate_matrix<T>(matrix: &Matrix<T>) -> bool {
+    matrix.iter().all(|row| row.len() == matrix[0].len())
+}
+
+// A single forward elimination pass, succeeding only if every pivot is non-zero. Callers needing the
+// inverse itself should just call `invert`.
 pub(crate) fn is_invertible<F: PrimeField>(matrix: &Matrix<F>) -> bool {
-    is_square(matrix) && invert(matrix).is_some()
+    lu_with_parity(matrix).is_some()
 }
 
 fn scalar_mul<F: PrimeField>(scalar: F, matrix: &Matrix<F>) -> Matrix<F> {
@@ -49,6 +83,19 @@ fn scalar_mul<F: PrimeField>(scalar: F, matrix: &Matrix<F>) -> Matrix<F> {
         .collect::<Vec<_>>()
 }
 
+/// Multiply every entry of `matrix` by `scalar`.
+///
+/// Panics if `matrix` is not rectangular.
+pub fn scale_matrix<F: PrimeField>(scalar: F, matrix: &Matrix<F>) -> Matrix<F> {
+    assert!(validate_matrix(matrix), "not a matrix");
+    scalar_mul(scalar, matrix)
+}
+
+/// Multiply every element of `v` by `scalar`.
+pub fn scale_vec<F: PrimeField>(scalar: F, v: &[F]) -> Vec<F> {
+    scalar_vec_mul(scalar, v)
+}
+
 fn scalar_vec_mul<F: PrimeField>(scalar: F, vec: &[F]) -> Vec<F> {
     vec.iter()
         .map(|val| {
@@ -59,34 +106,140 @@ fn scalar_vec_mul<F: PrimeField>(scalar: F, vec: &[F]) -> Vec<F> {
         .collect::<Vec<_>>()
 }
 
-pub fn mat_mul<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Option<Matrix<F>> {
-    if rows(a) != columns(b) {
+/// Only checked in debug builds: `matrix` is well-formed and `rows`×`columns`.
+fn debug_assert_dimensions<T>(matrix: &Matrix<T>, rows: usize, columns: usize) {
+    debug_assert!(validate_matrix(matrix), "not a matrix");
+    debug_assert_eq!(matrix.len(), rows, "matrix has the wrong number of rows");
+    debug_assert!(
+        matrix.iter().all(|row| row.len() == columns),
+        "matrix has the wrong number of columns"
+    );
+}
+
+/// Multiply `a` by `b` over any `Ring`, or `None` if `columns(a) != rows(b)`.
+pub fn mat_mul<R: ring::Ring>(a: &Matrix<R>, b: &Matrix<R>) -> Option<Matrix<R>> {
+    debug_assert!(validate_matrix(a) && validate_matrix(b), "not a matrix");
+    if columns(a) != rows(b) {
         return None;
     };
 
-    let b_t = transpose(b);
+    let res = mat_mul_transposed(a, &transpose(b));
+    debug_assert_dimensions(&res, rows(a), columns(b));
 
-    let res = a
-        .iter()
+    Some(res)
+}
+
+// Multiply `a` by the matrix whose transpose is `b_t`, i.e. whose columns are the rows of `b_t`.
+fn mat_mul_transposed<R: ring::Ring>(a: &Matrix<R>, b_t: &Matrix<R>) -> Matrix<R> {
+    a.iter()
         .map(|input_row| {
             b_t.iter()
                 .map(|transposed_column| vec_mul(input_row, transposed_column))
                 .collect()
         })
-        .collect();
+        .collect()
+}
+
+/// `mat_mul` for square matrices whose size is known at compile time, e.g. the MDS matrix of a given arity. Stored as
+/// arrays, they need no allocation, and the loop bounds are constants the compiler can unroll.
+#[allow(clippy::needless_range_loop)]
+pub fn mat_mul_array<F: PrimeField, const N: usize>(
+    a: &[[F; N]; N],
+    b: &[[F; N]; N],
+) -> [[F; N]; N] {
+    let mut res = [[F::zero(); N]; N];
+    for i in 0..N {
+        for j in 0..N {
+            for k in 0..N {
+                res[i][j] += a[i][k] * b[k][j];
+            }
+        }
+    }
+    res
+}
+
+/// Multiply `mats` left to right. All dimensions are checked before any multiplication, returning `None` if any
+/// adjacent pair is incompatible or `mats` is empty.
+pub fn mat_mul_chain<F: PrimeField>(mats: &[Matrix<F>]) -> Option<Matrix<F>> {
+    debug_assert!(mats.iter().all(validate_matrix), "not a matrix");
+    let (first, rest) = mats.split_first()?;
+    if mats
+        .windows(2)
+        .any(|pair| columns(&pair[0]) != rows(&pair[1]))
+    {
+        return None;
+    }
+
+    let res = rest.iter().fold(first.clone(), |acc, m| {
+        mat_mul_transposed(&acc, &transpose(m))
+    });
+    debug_assert_dimensions(&res, rows(first), columns(mats.last()?));
 
     Some(res)
 }
 
-fn vec_mul<F: PrimeField>(a: &[F], b: &[F]) -> F {
-    a.iter().zip(b).fold(F::zero(), |mut acc, (v1, v2)| {
-        let mut tmp = *v1;
+fn vec_mul<R: ring::Ring>(a: &[R], b: &[R]) -> R {
+    if cfg!(feature = "fast-field") && a.len() >= VEC_MUL_BATCH_THRESHOLD {
+        return vec_mul_batched(a, b);
+    }
+
+    a.iter().zip(b).fold(R::zero(), |mut acc, (v1, v2)| {
+        let mut tmp = v1.clone();
         tmp.mul_assign(v2);
         acc.add_assign(&tmp);
         acc
     })
 }
 
+pub fn inner_product<F: PrimeField>(a: &[F], b: &[F]) -> F {
+    assert_eq!(a.len(), b.len(), "Vectors must be the same size.");
+    vec_mul(a, b)
+}
+
+/// The quadratic form `vᵀ·M·v`.
+pub fn quadratic_form<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> F {
+    inner_product(v, &apply_matrix(m, v))
+}
+
+/// The Frobenius inner product `Σᵢⱼ a[i][j]·b[i][j]`, or `None` if `a` and `b` have different dimensions.
+pub fn frobenius_inner<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Option<F> {
+    let same_shape = rows(a) == rows(b) && a.iter().zip(b).all(|(a, b)| a.len() == b.len());
+    if !same_shape {
+        return None;
+    }
+    Some(inner_product(&a.concat(), &b.concat()))
+}
+
+/// Vectors at least this long use `vec_mul_batched` when the `fast-field` feature is enabled.
+const VEC_MUL_BATCH_THRESHOLD: usize = 8;
+
+/// Same result as the serial `vec_mul` (ring addition is associative), but accumulates into four independent
+/// sums, so consecutive multiplications don't wait on the previous addition.
+fn vec_mul_batched<R: ring::Ring>(a: &[R], b: &[R]) -> R {
+    let mut acc = [R::zero(), R::zero(), R::zero(), R::zero()];
+    let mut a_chunks = a.chunks_exact(4);
+    let mut b_chunks = b.chunks_exact(4);
+
+    for (a, b) in (&mut a_chunks).zip(&mut b_chunks) {
+        for i in 0..4 {
+            let mut tmp = a[i].clone();
+            tmp.mul_assign(&b[i]);
+            acc[i].add_assign(&tmp);
+        }
+    }
+    for (a, b) in a_chunks.remainder().iter().zip(b_chunks.remainder()) {
+        let mut tmp = a.clone();
+        tmp.mul_assign(b);
+        acc[0].add_assign(&tmp);
+    }
+
+    let [mut sum, acc1, mut acc23, acc3] = acc;
+    sum.add_assign(&acc1);
+    acc23.add_assign(&acc3);
+    sum.add_assign(&acc23);
+    sum
+}
+
 pub fn vec_add<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
     a.iter()
         .zip(b.iter())
@@ -109,6 +262,55 @@ pub fn vec_sub<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
         .collect::<Vec<_>>()
 }
 
+/// Like `vec_add`, but fails rather than truncating to the shorter vector if the lengths differ.
+pub fn vec_add_checked<F: PrimeField>(a: &[F], b: &[F]) -> Result<Vec<F>, Error> {
+    check_lengths(a, b)?;
+    Ok(vec_add(a, b))
+}
+
+/// Like `vec_sub`, but fails rather than truncating to the shorter vector if the lengths differ.
+pub fn vec_sub_checked<F: PrimeField>(a: &[F], b: &[F]) -> Result<Vec<F>, Error> {
+    check_lengths(a, b)?;
+    Ok(vec_sub(a, b))
+}
+
+fn check_lengths<F>(a: &[F], b: &[F]) -> Result<(), Error> {
+    if a.len() != b.len() {
+        return Err(Error::InputLengthMismatch {
+            expected: a.len(),
+            got: b.len(),
+        });
+    }
+    Ok(())
+}
+
+/// Element-wise product of two vectors.
+pub fn hadamard_vec_mul<F: PrimeField>(a: &[F], b: &[F]) -> Vec<F> {
+    a.iter()
+        .zip(b.iter())
+        .map(|(a, b)| {
+            let mut res = *a;
+            res.mul_assign(b);
+            res
+        })
+        .collect::<Vec<_>>()
+}
+
+/// Element-wise product of two matrices. Returns `None` if their dimensions differ.
+pub fn hadamard_mat_mul<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Option<Matrix<F>> {
+    if rows(a) != rows(b) || columns(a) != columns(b) {
+        return None;
+    }
+
+    let res = a
+        .iter()
+        .zip(b.iter())
+        .map(|(a_row, b_row)| hadamard_vec_mul(a_row, b_row))
+        .collect();
+
+    Some(res)
+}
+
 /// Left-multiply a vector by a square matrix of same size: MV where V is considered a column vector.
 pub fn left_apply_matrix<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Vec<F> {
     assert!(is_square(m), "Only square matrix can be applied to vector.");
@@ -130,41 +332,326 @@ pub fn left_apply_matrix<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Vec<F> {
     result
 }
 
-/// Right-multiply a vector by a square matrix  of same size: VM where V is considered a row vector.
-pub fn apply_matrix<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Vec<F> {
+// Counts the calls to `apply_matrix` which took the identity shortcut, so tests can observe it.
+#[cfg(test)]
+thread_local! {
+    static IDENTITY_SHORTCUTS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// Right-multiply a vector by a square matrix  of same size: VM where V is considered a row vector, over any `Ring`.
+/// If `m` is the identity, `v` is returned without any multiplication.
+pub fn apply_matrix<R: ring::Ring>(m: &Matrix<R>, v: &[R]) -> Vec<R> {
+    if rows(m) == v.len() && is_identity_quick(m) {
+        #[cfg(test)]
+        IDENTITY_SHORTCUTS.with(|count| count.set(count.get() + 1));
+        return v.to_vec();
+    }
+    let mut result = vec![R::zero(); v.len()];
+    apply_matrix_into(m, v, &mut result);
+
+    result
+}
+
+/// Like `apply_matrix`, but writes the result into `out` (which must be the same size as `v`) instead of allocating.
+pub fn apply_matrix_into<R: ring::Ring>(m: &Matrix<R>, v: &[R], out: &mut [R]) {
+    out.iter_mut().for_each(|val| *val = R::zero());
+    add_matrix_product(m, v, out);
+}
+
+/// `vec_add(&apply_matrix(m, v), rc)` in a single pass: each output element starts from its round constant rather
+/// than zero, so no intermediate product vector is allocated.
+pub fn apply_matrix_add<F: PrimeField>(m: &Matrix<F>, v: &[F], rc: &[F]) -> Vec<F> {
+    let mut result = rc.to_vec();
+    add_matrix_product(m, v, &mut result);
+
+    result
+}
+
+// Add the vector-matrix product `v·m` to `out`.
+fn add_matrix_product<R: ring::Ring>(m: &Matrix<R>, v: &[R], out: &mut [R]) {
+    debug_assert!(validate_matrix(m), "not a matrix");
     assert!(is_square(m), "Only square matrix can be applied to vector.");
     assert_eq!(
         rows(m),
         v.len(),
         "Matrix can only be applied to vector of same size."
     );
+    assert_eq!(
+        v.len(),
+        out.len(),
+        "Output must be the same size as vector."
+    );
 
-    let mut result = vec![F::zero(); v.len()];
-    for (j, val) in result.iter_mut().enumerate() {
+    for (j, val) in out.iter_mut().enumerate() {
         for (i, row) in m.iter().enumerate() {
-            let mut tmp = row[j];
+            let mut tmp = row[j].clone();
             tmp.mul_assign(&v[i]);
             val.add_assign(&tmp);
         }
     }
+}
+
+/// Same result as `apply_matrix`, but accumulates `v[i]·m[i]` row by row, so `m` is read in storage order rather than
+/// column by column.
+pub fn apply_matrix_rowmajor<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Vec<F> {
+    assert!(is_square(m), "Only square matrix can be applied to vector.");
+    assert_eq!(
+        rows(m),
+        v.len(),
+        "Matrix can only be applied to vector of same size."
+    );
 
+    let mut result = vec![F::zero(); v.len()];
+    for (row, scalar) in m.iter().zip(v) {
+        for (acc, val) in result.iter_mut().zip(row) {
+            *acc += *val * scalar;
+        }
+    }
     result
 }
 
+/// `apply_matrix` for each of `num_states` states stored interleaved in `states`, i.e. element `i` of state `s` at
+/// `states[i * num_states + s]`. The results are written to `out` in the same layout.
+pub fn apply_matrix_strided<F: PrimeField>(
+    m: &Matrix<F>,
+    states: &[F],
+    num_states: usize,
+    out: &mut [F],
+) {
+    debug_assert!(validate_matrix(m), "not a matrix");
+    assert!(is_square(m), "Only square matrix can be applied to vector.");
+    assert_eq!(
+        rows(m) * num_states,
+        states.len(),
+        "States must each be the same size as matrix."
+    );
+    assert_eq!(
+        states.len(),
+        out.len(),
+        "Output must be the same size as states."
+    );
+
+    for (j, out_row) in out.chunks_mut(num_states.max(1)).enumerate() {
+        out_row.iter_mut().for_each(|val| *val = F::zero());
+        for (i, row) in m.iter().enumerate() {
+            let state_row = &states[i * num_states..(i + 1) * num_states];
+            for (val, state_val) in out_row.iter_mut().zip(state_row) {
+                let mut tmp = row[j];
+                tmp.mul_assign(state_val);
+                val.add_assign(&tmp);
+            }
+        }
+    }
+}
+
+/// Like `apply_matrix`, but returns an error rather than panicking if `m` is not square or `v` is not the same size,
+/// e.g. when `m` and `v` are supplied externally.
+pub fn try_apply_matrix<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Result<Vec<F>, MatrixError> {
+    if !validate_matrix(m) || !is_square(m) {
+        return Err(MatrixError::NotSquare);
+    }
+    if rows(m) != v.len() {
+        return Err(MatrixError::SizeMismatch {
+            expected: rows(m),
+            got: v.len(),
+        });
+    }
+    Ok(apply_matrix(m, v))
+}
+
+/// Like `try_apply_matrix`, but for the representations of `m` and `v`, e.g. as deserialized from untrusted input.
+/// Every entry is checked to be the canonical representation of a field element before the matrix is applied.
+pub fn apply_matrix_validated<F: PrimeField>(
+    m: &Matrix<F::Repr>,
+    v: &[F::Repr],
+) -> Result<Vec<F>, MatrixError> {
+    let from_repr = |repr: &F::Repr| -> Result<F, MatrixError> {
+        Option::from(F::from_repr(*repr)).ok_or(MatrixError::NonCanonical)
+    };
+    let m = m
+        .iter()
+        .map(|row| row.iter().map(from_repr).collect())
+        .collect::<Result<Matrix<F>, _>>()?;
+    let v = v.iter().map(from_repr).collect::<Result<Vec<F>, _>>()?;
+
+    try_apply_matrix(&m, &v)
+}
+
+/// Check `apply_matrix(m, v) == left_apply_matrix(transpose(m), v)`, i.e. `V·M = (Mᵀ·Vᵀ)ᵀ`.
+pub fn check_apply_transpose_relation<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> bool {
+    apply_matrix(m, v) == left_apply_matrix(&transpose(m), v)
+}
+
+/// Generalization of `apply_matrix` (VM) to vectors of any type which can be scaled by a matrix entry and summed,
+/// e.g. symbolic values such as linear combinations of constraint system variables.
+pub fn apply_matrix_symbolic<F, T, S>(m: &Matrix<F>, v: &[T], scale: S) -> Vec<T>
+where
+    F: PrimeField,
+    T: Clone + AddAssign,
+    S: Fn(&F, &T) -> T,
+{
+    assert!(is_square(m), "Only square matrix can be applied to vector.");
+    assert_eq!(
+        rows(m),
+        v.len(),
+        "Matrix can only be applied to vector of same size."
+    );
+    assert!(!v.is_empty(), "Cannot apply empty matrix.");
+
+    (0..v.len())
+        .map(|j| {
+            let mut acc = scale(&m[0][j], &v[0]);
+            for (row, val) in m.iter().zip(v).skip(1) {
+                acc += scale(&row[j], val);
+            }
+            acc
+        })
+        .collect()
+}
+
+/// Apply `m` to `v` (as in `apply_matrix`) `times` times, without forming the matrix power.
+pub fn apply_matrix_iterated<F: PrimeField>(m: &Matrix<F>, v: &[F], times: usize) -> Vec<F> {
+    (0..times).fold(v.to_vec(), |acc, _| apply_matrix(m, &acc))
+}
+
+/// Raise the square matrix `m` to the power `n`.
+pub fn matrix_power<F: PrimeField>(m: &Matrix<F>, n: usize) -> Matrix<F> {
+    assert!(is_square(m), "Only square matrix can be raised to a power.");
+    (0..n).fold(make_identity(rows(m)), |acc, _| {
+        mat_mul(&acc, m).expect("square matrices of same size can be multiplied")
+    })
+}
+
+/// Sum of the diagonal entries of a square matrix.
+pub fn trace<F: PrimeField>(m: &Matrix<F>) -> F {
+    assert!(is_square(m), "Only square matrix has a trace.");
+    m.iter().enumerate().fold(F::zero(), |mut acc, (i, row)| {
+        acc.add_assign(&row[i]);
+        acc
+    })
+}
+
+/// Coefficients of the characteristic polynomial `det(xI - M)`, lowest degree first,
+/// computed with the Faddeev–LeVerrier algorithm. The result has `rows(m) + 1` entries, the last of which is one.
+#[allow(clippy::needless_range_loop)]
+pub fn characteristic_poly<F: PrimeField>(m: &Matrix<F>) -> Vec<F> {
+    assert!(
+        is_square(m),
+        "Only square matrix has a characteristic polynomial."
+    );
+    let n = rows(m);
+
+    let mut coeffs = vec![F::zero(); n + 1];
+    coeffs[n] = F::one();
+
+    // M_0 = 0
+    let mut m_k = vec![vec![F::zero(); n]; n];
+    for k in 1..=n {
+        // M_k = A·M_{k-1} + c_{n-k+1}·I
+        m_k = mat_mul(m, &m_k).expect("square matrices of same size can be multiplied");
+        for i in 0..n {
+            m_k[i][i].add_assign(&coeffs[n - k + 1]);
+        }
+
+        // c_{n-k} = -tr(A·M_k) / k
+        let am_k = mat_mul(m, &m_k).expect("square matrices of same size can be multiplied");
+        let inv_k = F::from(k as u64).invert().unwrap();
+        let mut c = trace(&am_k);
+        c.mul_assign(&inv_k);
+        coeffs[n - k] = -c;
+    }
+    coeffs
+}
+
+/// Fold each row of `m` separately, starting from `init`.
+pub fn reduce_rows<F, T, R>(m: &Matrix<F>, init: T, f: R) -> Vec<T>
+where
+    F: PrimeField,
+    T: Clone,
+    R: Fn(T, &F) -> T,
+{
+    m.iter()
+        .map(|row| row.iter().fold(init.clone(), &f))
+        .collect()
+}
+
+/// Fold the corresponding entries of `a` and `b` together, row by row, starting from `init`. Returns `None` if `a`
+/// and `b` do not have the same dimensions.
+pub fn zip_reduce<F, T, R>(a: &Matrix<F>, b: &Matrix<F>, init: T, f: R) -> Option<T>
+where
+    F: PrimeField,
+    R: Fn(T, &F, &F) -> T,
+{
+    let same_shape = a.len() == b.len()
+        && a.iter()
+            .zip(b)
+            .all(|(a_row, b_row)| a_row.len() == b_row.len());
+    if !same_shape {
+        return None;
+    }
+    Some(
+        a.iter()
+            .flatten()
+            .zip(b.iter().flatten())
+            .fold(init, |acc, (x, y)| f(acc, x, y)),
+    )
+}
+
+/// The number of entries in which `a` and `b` differ, or `None` if they do not have the same dimensions.
+pub fn count_differing_entries<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Option<usize> {
+    zip_reduce(a, b, 0, |n, x, y| if x == y { n } else { n + 1 })
+}
+
+pub fn row_sums<F: PrimeField>(m: &Matrix<F>) -> Vec<F> {
+    reduce_rows(m, F::zero(), |mut acc, val| {
+        acc.add_assign(val);
+        acc
+    })
+}
+
+/// The sum of all entries of `m`: a cheap checksum for comparing supposedly identical matrices before comparing
+/// them entry by entry.
+pub fn sum_entries<F: PrimeField>(m: &Matrix<F>) -> F {
+    row_sums(m).iter().fold(F::zero(), |mut acc, val| {
+        acc.add_assign(val);
+        acc
+    })
+}
+
 #[allow(clippy::needless_range_loop)]
-pub fn transpose<F: PrimeField>(matrix: &Matrix<F>) -> Matrix<F> {
+pub fn transpose<T: Clone>(matrix: &Matrix<T>) -> Matrix<T> {
     let size = rows(matrix);
-    let mut new = Vec::with_capacity(size);
-    for j in 0..size {
+    let mut new = Vec::with_capacity(columns(matrix));
+    for j in 0..columns(matrix) {
         let mut row = Vec::with_capacity(size);
         for i in 0..size {
-            row.push(matrix[i][j])
+            row.push(matrix[i][j].clone())
         }
         new.push(row);
     }
     new
 }
 
+/// Flatten `matrix` column by column, for interop with column-major libraries.
+pub fn to_column_major<F: PrimeField>(matrix: &Matrix<F>) -> Vec<F> {
+    (0..columns(matrix))
+        .flat_map(|j| matrix.iter().map(move |row| row[j]))
+        .collect()
+}
+
+/// Inverse of `to_column_major`: build a `rows`×`cols` matrix from column-major `data`. Returns `None` if `data`
+/// does not have exactly `rows · cols` elements.
+pub fn from_column_major<F: PrimeField>(data: &[F], rows: usize, cols: usize) -> Option<Matrix<F>> {
+    if data.len() != rows * cols {
+        return None;
+    }
+    Some(
+        (0..rows)
+            .map(|i| (0..cols).map(|j| data[j * rows + i]).collect())
+            .collect(),
+    )
+}
+
 #[allow(clippy::needless_range_loop)]
 pub fn make_identity<F: PrimeField>(size: usize) -> Matrix<F> {
     let mut result = vec![vec![F::zero(); size]; size];
@@ -174,6 +661,76 @@ pub fn make_identity<F: PrimeField>(size: usize) -> Matrix<F> {
     result
 }
 
+/// Render `m` one row per line, with each entry as the big-endian hex of its integer value, e.g.
+/// `[0x1, 0x0]`.
+pub fn format_matrix<F: PrimeField>(m: &Matrix<F>) -> String {
+    let mut out = String::new();
+    for row in m {
+        let entries: Vec<String> = row.iter().map(format_element).collect();
+        writeln!(out, "[{}]", entries.join(", ")).expect("writing to a String cannot fail");
+    }
+    out
+}
+
+pub(crate) fn format_element<F: PrimeField>(x: &F) -> String {
+    let mut digits = String::new();
+    for byte in scalar_to_bytes_le(x).iter().rev() {
+        write!(digits, "{:02x}", byte).expect("writing to a String cannot fail");
+    }
+    let digits = digits.trim_start_matches('0');
+
+    format!("0x{}", if digits.is_empty() { "0" } else { digits })
+}
+
+/// Parse a field element from big-endian hex, with or without a `0x` prefix, as rendered by `format_matrix`. Returns
+/// `None` unless the value is less than the field modulus.
+pub(crate) fn parse_element<F: PrimeField>(hex: &str) -> Option<F> {
+    let digits = hex
+        .strip_prefix("0x")
+        .or_else(|| hex.strip_prefix("0X"))
+        .unwrap_or(hex);
+
+    let mut bytes = vec![0u8; (F::NUM_BITS as usize + 7) / 8];
+    if digits.is_empty() || digits.len() > 2 * bytes.len() {
+        return None;
+    }
+    // Walk the digits from least significant, filling the little-endian bytes.
+    for (i, digit) in digits.chars().rev().enumerate() {
+        let nibble = digit.to_digit(16)? as u8;
+        bytes[i / 2] |= nibble << (4 * (i % 2));
+    }
+
+    scalar_from_bytes_le(&bytes)
+}
+
+/// Pseudo-random `rows`×`cols` matrix, filled from a ChaCha8 generator seeded with `seed`, so that the same seed
+/// always gives the same matrix, e.g. for property tests. Not suitable for generating secrets.
+#[cfg(any(test, feature = "random"))]
+pub fn random_matrix<F: PrimeField>(rows: usize, cols: usize, seed: u64) -> Matrix<F> {
+    use rand_chacha::rand_core::SeedableRng;
+
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+    (0..rows)
+        .map(|_| (0..cols).map(|_| F::random(&mut rng)).collect())
+        .collect()
+}
+
+/// `M·v`, with `v` a column vector, computed entry by entry as `Σ_j m[i][j]·v[j]`. This is deliberately naive, as
+/// a reference for the multiplication direction of `left_apply_matrix` and `apply_matrix` in tests.
+#[cfg(test)]
+#[allow(clippy::needless_range_loop)]
+pub(crate) fn reference_mat_vec<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Vec<F> {
+    let mut result = Vec::with_capacity(m.len());
+    for i in 0..m.len() {
+        let mut sum = F::zero();
+        for j in 0..v.len() {
+            sum += m[i][j] * v[j];
+        }
+        result.push(sum);
+    }
+    result
+}
+
 pub fn kronecker_delta<F: PrimeField>(i: usize, j: usize) -> F {
     if i == j {
         F::one()
@@ -193,6 +750,101 @@ pub fn is_identity<F: PrimeField>(matrix: &Matrix<F>) -> bool {
     true
 }
 
+/// Like `is_identity`, but cheap enough to check before applying a matrix: the diagonal is checked first, and
+/// each row is abandoned at its first entry ruling out the identity, so a dense matrix is usually rejected after a
+/// single comparison. Ragged or non-square input is never the identity.
+pub fn is_identity_quick<R: ring::Ring>(matrix: &Matrix<R>) -> bool {
+    let size = rows(matrix);
+    matrix.iter().all(|row| row.len() == size)
+        && matrix.iter().enumerate().all(|(i, row)| row[i] == R::one())
+        && matrix.iter().enumerate().all(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .all(|(j, val)| i == j || *val == R::zero())
+        })
+}
+
+/// Returns `true` if `m` is its own inverse, i.e. `m·m` is the identity.
+pub fn is_involution<F: PrimeField>(m: &Matrix<F>) -> bool {
+    is_square(m) && mat_mul(m, m).filter(is_identity).is_some()
+}
+
+/// A permutation matrix has exactly one `one` in each row and each column, and is `zero` everywhere else.
+pub fn is_permutation_matrix<F: PrimeField>(matrix: &Matrix<F>) -> bool {
+    if !is_square(matrix) {
+        return false;
+    }
+    let size = rows(matrix);
+    let mut column_seen = vec![false; size];
+
+    for row in matrix {
+        let mut row_one = None;
+        for (j, val) in row.iter().enumerate() {
+            if *val == F::one() {
+                if row_one.is_some() || column_seen[j] {
+                    return false;
+                }
+                row_one = Some(j);
+                column_seen[j] = true;
+            } else if *val != F::zero() {
+                return false;
+            }
+        }
+        if row_one.is_none() {
+            return false;
+        }
+    }
+    true
+}
+
+/// Reorder `v` by the index permutation `perm`, so that `result[i] = v[perm[i]]`, e.g. to check that the MDS layer
+/// diffuses regardless of input order.
+///
+/// # Panics
+///
+/// Panics if `perm` is not a permutation of `0..v.len()`.
+pub fn permute_state<F: PrimeField>(v: &[F], perm: &[usize]) -> Vec<F> {
+    assert_eq!(
+        v.len(),
+        perm.len(),
+        "Permutation must be the same size as the state."
+    );
+    let mut seen = vec![false; perm.len()];
+    for &index in perm {
+        assert!(
+            index < perm.len() && !seen[index],
+            "invalid permutation of state indices"
+        );
+        seen[index] = true;
+    }
+
+    perm.iter().map(|&index| v[index]).collect()
+}
+
+/// Returns `true` if `b` can be obtained by reordering the rows of `a`.
+pub fn equal_up_to_row_permutation<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> bool {
+    if rows(a) != rows(b) {
+        return false;
+    }
+    let mut used = vec![false; rows(b)];
+
+    a.iter().all(|a_row| {
+        let found = b
+            .iter()
+            .enumerate()
+            .find(|(i, b_row)| !used[*i] && a_row == *b_row)
+            .map(|(i, _)| i);
+
+        match found {
+            Some(i) => {
+                used[i] = true;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
 pub fn is_square<T>(matrix: &Matrix<T>) -> bool {
     rows(matrix) == columns(matrix)
 }
@@ -218,25 +870,163 @@ pub fn minor<F: PrimeField>(matrix: &Matrix<F>, i: usize, j: usize) -> Matrix<F>
     new
 }
 
-// Assumes matrix is partially reduced to upper triangular. `column` is the column to eliminate from all rows.
-// Returns `None` if either:
-//   - no non-zero pivot can be found for `column`
-//   - `column` is not the first
-fn eliminate<F: PrimeField>(
+/// The block of `matrix` at the intersection of `rows` and `cols`.
+pub fn submatrix<F: PrimeField>(
+    matrix: &Matrix<F>,
+    rows: core::ops::Range<usize>,
+    cols: core::ops::Range<usize>,
+) -> Matrix<F> {
+    matrix[rows]
+        .iter()
+        .map(|row| row[cols.clone()].to_vec())
+        .collect()
+}
+
+/// Returns the index of the first row at or below `start_row` with a non-zero entry in `column`.
+pub fn find_pivot<F: PrimeField>(
     matrix: &Matrix<F>,
     column: usize,
-    shadow: &mut Matrix<F>,
-) -> Option<Matrix<F>> {
-    let zero = F::zero();
-    let pivot_index = (0..rows(matrix))
-        .find(|&i| matrix[i][column] != zero && (0..column).all(|j| matrix[i][j] == zero))?;
+    start_row: usize,
+) -> Option<usize> {
+    (start_row..rows(matrix)).find(|&i| matrix[i][column] != F::zero())
+}
 
-    let pivot = &matrix[pivot_index];
-    let pivot_val = pivot[column];
+/// Determinant by cofactor expansion along the first row. This is O(n!), so only use it for small matrices.
+pub fn determinant<F: PrimeField>(matrix: &Matrix<F>) -> F {
+    assert!(is_square(matrix), "Only square matrix has a determinant.");
+    match rows(matrix) {
+        0 => F::one(),
+        1 => matrix[0][0],
+        _ => matrix[0]
+            .iter()
+            .enumerate()
+            .fold(F::zero(), |mut acc, (j, val)| {
+                let mut term = *val;
+                term.mul_assign(&determinant(&minor(matrix, 0, j)));
+                if j % 2 == 0 {
+                    acc.add_assign(&term);
+                } else {
+                    acc.sub_assign(&term);
+                }
+                acc
+            }),
+    }
+}
 
-    // This should never fail since we have a non-zero `pivot_val` if we got here.
-    let inv_pivot = Option::from(pivot_val.invert())?;
-    let mut result = Vec::with_capacity(matrix.len());
+/// The matrix of cofactors `(-1)^(i+j)·det(minor(matrix, i, j))`. Like `determinant`, this is O(n!) per entry.
+pub fn cofactor_matrix<F: PrimeField>(matrix: &Matrix<F>) -> Matrix<F> {
+    assert!(is_square(matrix));
+    let size = rows(matrix);
+    if size == 1 {
+        return vec![vec![F::one()]];
+    }
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| {
+                    let d = determinant(&minor(matrix, i, j));
+                    if (i + j) % 2 == 0 {
+                        d
+                    } else {
+                        -d
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// The transposed cofactor matrix, satisfying `matrix·adjugate(matrix) = det(matrix)·I` even when `matrix` is
+/// singular, so that no division is needed.
+pub fn adjugate<F: PrimeField>(matrix: &Matrix<F>) -> Matrix<F> {
+    transpose(&cofactor_matrix(matrix))
+}
+
+/// LU decomposition with partial pivoting: returns `(L, U, swaps)` such that `L·U` is `matrix` with its rows
+/// permuted by `swaps` row swaps, `L` is unit lower triangular and `U` is upper triangular. Returns `None` if
+/// `matrix` is not square or is singular.
+pub fn lu_with_parity<F: PrimeField>(matrix: &Matrix<F>) -> Option<(Matrix<F>, Matrix<F>, usize)> {
+    if !is_square(matrix) {
+        return None;
+    }
+    let size = rows(matrix);
+    let mut l = vec![vec![F::zero(); size]; size];
+    let mut u = matrix.clone();
+    let mut swaps = 0;
+
+    for column in 0..size {
+        let pivot_index = find_pivot(&u, column, column)?;
+        if pivot_index != column {
+            u.swap(column, pivot_index);
+            l.swap(column, pivot_index);
+            swaps += 1;
+        }
+
+        let inv_pivot: F = Option::from(u[column][column].invert())?;
+        for i in column + 1..size {
+            let mut factor = u[i][column];
+            factor.mul_assign(&inv_pivot);
+            l[i][column] = factor;
+            u[i] = vec_sub(&u[i], &scalar_vec_mul(factor, &u[column]));
+        }
+    }
+    for (i, row) in l.iter_mut().enumerate() {
+        row[i] = F::one();
+    }
+
+    Some((l, u, swaps))
+}
+
+/// Determinant as `(-1)^swaps · prod(diag(U))` from `lu_with_parity`. Unlike `determinant`, this is O(n³).
+pub fn determinant_via_elimination<F: PrimeField>(matrix: &Matrix<F>) -> F {
+    assert!(is_square(matrix));
+    match lu_with_parity(matrix) {
+        Some((_, u, swaps)) => {
+            let product = u
+                .iter()
+                .enumerate()
+                .fold(F::one(), |acc, (i, row)| acc * row[i]);
+            if swaps % 2 == 0 {
+                product
+            } else {
+                -product
+            }
+        }
+        None => F::zero(),
+    }
+}
+
+/// The determinants of the top-left `k×k` submatrices of the square `matrix`, for `k = 1..=n`. Gaussian elimination
+/// without row swaps succeeds exactly when all of them are non-zero.
+pub fn leading_principal_minors<F: PrimeField>(matrix: &Matrix<F>) -> Vec<F> {
+    assert!(is_square(matrix));
+    (1..=rows(matrix))
+        .map(|k| {
+            let leading: Matrix<F> = matrix[..k].iter().map(|row| row[..k].to_vec()).collect();
+            determinant_via_elimination(&leading)
+        })
+        .collect()
+}
+
+// Assumes matrix is partially reduced to upper triangular. `column` is the column to eliminate from all rows.
+// Returns `None` if either:
+//   - no non-zero pivot can be found for `column`
+//   - `column` is not the first
+fn eliminate<F: PrimeField>(
+    matrix: &Matrix<F>,
+    column: usize,
+    shadow: &mut Matrix<F>,
+) -> Option<Matrix<F>> {
+    let zero = F::zero();
+    let pivot_index = (0..rows(matrix))
+        .find(|&i| matrix[i][column] != zero && (0..column).all(|j| matrix[i][j] == zero))?;
+
+    let pivot = &matrix[pivot_index];
+    let pivot_val = pivot[column];
+
+    // This should never fail since we have a non-zero `pivot_val` if we got here.
+    let inv_pivot = Option::from(pivot_val.invert())?;
+    let mut result = Vec::with_capacity(matrix.len());
     result.push(pivot.clone());
 
     for (i, row) in matrix.iter().enumerate() {
@@ -349,11 +1139,267 @@ fn reduce_to_identity<F: PrimeField>(
 
 //
 pub(crate) fn invert<F: PrimeField>(matrix: &Matrix<F>) -> Option<Matrix<F>> {
+    debug_assert!(validate_matrix(matrix), "not a matrix");
     let mut shadow = make_identity(columns(matrix));
     let ut = upper_triangular(matrix, &mut shadow);
 
-    ut.and_then(|x| reduce_to_identity(&x, &mut shadow))
-        .and(Some(shadow))
+    let inverse = ut
+        .and_then(|x| reduce_to_identity(&x, &mut shadow))
+        .and(Some(shadow));
+    if let Some(inverse) = &inverse {
+        debug_assert_dimensions(inverse, rows(matrix), rows(matrix));
+    }
+    inverse
+}
+
+/// Invert `matrix` with a single Gauss-Jordan elimination pass. Returns `None` if `matrix` is not square or a
+/// zero pivot is hit (i.e. `matrix` is singular).
+pub fn invert_checked<F: PrimeField>(matrix: &Matrix<F>) -> Option<Matrix<F>> {
+    invert_in_place(matrix.clone())
+}
+
+/// Invert the diagonal matrix with diagonal `diag`, returning the diagonal matrix of the element-wise inverses
+/// without any elimination. Returns `None` if any entry of `diag` is zero.
+pub fn invert_diagonal<F: PrimeField>(diag: &[F]) -> Option<Matrix<F>> {
+    let inverses = diag
+        .iter()
+        .map(|d| Option::from(d.invert()))
+        .collect::<Option<Vec<F>>>()?;
+    Some(diagonal_matrix(&inverses))
+}
+
+fn diagonal_matrix<F: PrimeField>(diag: &[F]) -> Matrix<F> {
+    let mut result = vec![vec![F::zero(); diag.len()]; diag.len()];
+    for (i, d) in diag.iter().enumerate() {
+        result[i][i] = *d;
+    }
+    result
+}
+
+/// Like `invert_checked`, but consumes `matrix` and eliminates in place on the augmented rows `[A|I]`, so the only
+/// allocation is extending each row.
+pub fn invert_in_place<F: PrimeField>(mut matrix: Matrix<F>) -> Option<Matrix<F>> {
+    if !is_square(&matrix) {
+        return None;
+    }
+    let size = rows(&matrix);
+    for (i, row) in matrix.iter_mut().enumerate() {
+        row.reserve_exact(size);
+        row.extend((0..size).map(|j| kronecker_delta::<F>(i, j)));
+    }
+
+    gauss_jordan(&mut matrix, size)?;
+
+    for row in matrix.iter_mut() {
+        row.drain(..size);
+    }
+    Some(matrix)
+}
+
+// Reduce the leading `size` columns of the augmented `matrix` (`size` rows) to the identity, in place. Returns
+// `None` if they are singular.
+fn gauss_jordan<F: PrimeField>(matrix: &mut Matrix<F>, size: usize) -> Option<()> {
+    for column in 0..size {
+        let pivot_index = find_pivot(matrix, column, column)?;
+        matrix.swap(column, pivot_index);
+
+        let inv_pivot: F = Option::from(matrix[column][column].invert())?;
+        for val in matrix[column].iter_mut() {
+            val.mul_assign(&inv_pivot);
+        }
+
+        let (before, rest) = matrix.split_at_mut(column);
+        let (pivot, after) = rest.split_first_mut().expect("column is in bounds");
+        for row in before.iter_mut().chain(after.iter_mut()) {
+            let factor = row[column];
+            if factor == F::zero() {
+                continue;
+            }
+            for (val, pivot_val) in row.iter_mut().zip(pivot.iter()) {
+                let mut tmp = *pivot_val;
+                tmp.mul_assign(&factor);
+                val.sub_assign(&tmp);
+            }
+        }
+    }
+    Some(())
+}
+
+// Reduce the leading `columns` columns of the augmented `matrix` to reduced row echelon form, in place. Returns the
+// pivot columns, in order.
+fn reduce_to_echelon<F: PrimeField>(matrix: &mut Matrix<F>, columns: usize) -> Vec<usize> {
+    let mut pivots = Vec::new();
+    for column in 0..columns {
+        let row = pivots.len();
+        let pivot_index = match find_pivot(matrix, column, row) {
+            Some(pivot_index) => pivot_index,
+            None => continue,
+        };
+        matrix.swap(row, pivot_index);
+
+        let inv_pivot: F = Option::from(matrix[row][column].invert()).expect("pivot is non-zero");
+        for val in matrix[row].iter_mut() {
+            val.mul_assign(&inv_pivot);
+        }
+
+        let (before, rest) = matrix.split_at_mut(row);
+        let (pivot, after) = rest.split_first_mut().expect("row is in bounds");
+        for other in before.iter_mut().chain(after.iter_mut()) {
+            let factor = other[column];
+            if factor == F::zero() {
+                continue;
+            }
+            for (val, pivot_val) in other.iter_mut().zip(pivot.iter()) {
+                let mut tmp = *pivot_val;
+                tmp.mul_assign(&factor);
+                val.sub_assign(&tmp);
+            }
+        }
+        pivots.push(column);
+    }
+    pivots
+}
+
+/// The pivot columns of `m` after reduction to row echelon form, in order. These are the first linearly independent
+/// columns of `m`: every other column is a combination of the pivot columns before it, and their number is the rank
+/// of `m`.
+pub fn pivot_columns<F: PrimeField>(m: &Matrix<F>) -> Vec<usize> {
+    let mut reduced = m.clone();
+    reduce_to_echelon(&mut reduced, columns(m))
+}
+
+/// A generalized inverse `G` of the `m×n` matrix `a`, satisfying `a·G·a = a` and `G·a·G = G`; if `a` is invertible,
+/// `G` is its inverse. This is not the Moore–Penrose inverse, which additionally requires `a·G` and `G·a` to be
+/// symmetric and need not exist over a finite field. Instead, with `[a | I]` reduced to reduced row echelon form
+/// `[R | P]`, row `c_i` of the `n×m` matrix `G` is row `i` of `P`, where `c_i` is the `i`th pivot column of `R`, and
+/// all other rows are zero.
+pub fn pseudo_inverse<F: PrimeField>(a: &Matrix<F>) -> Matrix<F> {
+    let (m, n) = (rows(a), columns(a));
+    let mut augmented: Matrix<F> = a
+        .iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let mut row = row.clone();
+            row.extend((0..m).map(|j| kronecker_delta::<F>(i, j)));
+            row
+        })
+        .collect();
+    let pivots = reduce_to_echelon(&mut augmented, n);
+
+    let mut result = vec![vec![F::zero(); m]; n];
+    for (i, column) in pivots.into_iter().enumerate() {
+        result[column] = augmented[i][n..].to_vec();
+    }
+    result
+}
+
+/// Solve `A·x = b` for `x` (as a column vector). Returns `None` if `a` is not square, its size doesn't match `b`, or
+/// it is singular.
+pub fn solve_linear_system<F: PrimeField>(a: &Matrix<F>, b: &[F]) -> Option<Vec<F>> {
+    if !is_square(a) || rows(a) != b.len() {
+        return None;
+    }
+    let size = b.len();
+    let mut augmented: Matrix<F> = a
+        .iter()
+        .zip(b)
+        .map(|(row, b)| {
+            let mut row = row.clone();
+            row.push(*b);
+            row
+        })
+        .collect();
+
+    gauss_jordan(&mut augmented, size)?;
+
+    Some(augmented.into_iter().map(|row| row[size]).collect())
+}
+
+/// The `j`th column of `M⁻¹`, found by solving `M·x = e_j` without computing the rest of the inverse.
+pub fn inverse_column<F: PrimeField>(m: &Matrix<F>, j: usize) -> Option<Vec<F>> {
+    let unit: Vec<F> = (0..rows(m)).map(|i| kronecker_delta(i, j)).collect();
+    solve_linear_system(m, &unit)
+}
+
+/// Same result as `apply_matrix(&invert(m)?, v)`, i.e. `v·M⁻¹`, but solves `x·M = v` directly rather than
+/// computing the inverse.
+pub fn apply_inverse<F: PrimeField>(m: &Matrix<F>, v: &[F]) -> Option<Vec<F>> {
+    solve_linear_system(&transpose(m), v)
+}
+
+/// Invert `matrix` by 2×2 block decomposition `[[A, B], [C, D]]`, where `A` is the leading `split`×`split` block,
+/// using the Schur complement `S = D - C·A⁻¹·B`. Returns `None` if `matrix` is not square, `split` does not
+/// leave two non-empty diagonal blocks, or either `A` or `S` is singular.
+pub fn invert_block<F: PrimeField>(matrix: &Matrix<F>, split: usize) -> Option<Matrix<F>> {
+    let size = rows(matrix);
+    if !is_square(matrix) || split == 0 || split >= size {
+        return None;
+    }
+
+    let a = submatrix(matrix, 0..split, 0..split);
+    let b = submatrix(matrix, 0..split, split..size);
+    let c = submatrix(matrix, split..size, 0..split);
+    let d = submatrix(matrix, split..size, split..size);
+
+    let a_inv = invert(&a)?;
+    let a_inv_b = mat_mul(&a_inv, &b)?;
+    let c_a_inv = mat_mul(&c, &a_inv)?;
+    let s = mat_sub(&d, &mat_mul(&c, &a_inv_b)?);
+    let s_inv = invert(&s)?;
+
+    let minus_one = -F::one();
+    // A⁻¹ + A⁻¹·B·S⁻¹·C·A⁻¹
+    let top_left = mat_add(&a_inv, &mat_mul(&mat_mul(&a_inv_b, &s_inv)?, &c_a_inv)?);
+    // -A⁻¹·B·S⁻¹
+    let top_right = scalar_mul(minus_one, &mat_mul(&a_inv_b, &s_inv)?);
+    // -S⁻¹·C·A⁻¹
+    let bottom_left = scalar_mul(minus_one, &mat_mul(&s_inv, &c_a_inv)?);
+
+    let top = top_left
+        .into_iter()
+        .zip(top_right)
+        .map(|(l, r)| [l, r].concat());
+    let bottom = bottom_left
+        .into_iter()
+        .zip(s_inv)
+        .map(|(l, r)| [l, r].concat());
+
+    Some(top.chain(bottom).collect())
+}
+
+fn mat_add<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Matrix<F> {
+    a.iter().zip(b).map(|(a, b)| vec_add(a, b)).collect()
+}
+
+fn mat_sub<F: PrimeField>(a: &Matrix<F>, b: &Matrix<F>) -> Matrix<F> {
+    a.iter().zip(b).map(|(a, b)| vec_sub(a, b)).collect()
+}
+
+/// Check that inverting `m` twice with `invert` gives back `m`, running the full elimination (including the shadow
+/// matrix) in both directions. Returns `false` if either inversion fails.
+pub fn check_double_inverse<F: PrimeField>(m: &Matrix<F>) -> bool {
+    invert(m).and_then(|m_inv| invert(&m_inv)).as_ref() == Some(m)
+}
+
+/// Check `S + M(B) = M(B + M⁻¹(S))`, which holds for any `s` and `b` if `m_inv` is the inverse of `m`.
+pub fn check_affine_commute<F: PrimeField>(
+    m: &Matrix<F>,
+    m_inv: &Matrix<F>,
+    s: &[F],
+    b: &[F],
+) -> bool {
+    let add_after_apply = vec_add(s, &apply_matrix(m, b));
+    let apply_after_add = apply_matrix(m, &vec_add(b, &apply_matrix(m_inv, s)));
+
+    add_after_apply == apply_after_add
+}
+
+/// Returns `(M⁻¹, (M⁻¹)ᵀ)`, computing the inverse only once.
+pub fn inverse_and_transpose<F: PrimeField>(matrix: &Matrix<F>) -> Option<(Matrix<F>, Matrix<F>)> {
+    let inverse = invert(matrix)?;
+    let inverse_transpose = transpose(&inverse);
+
+    Some((inverse, inverse_transpose))
 }
 
 #[cfg(test)]
@@ -361,18 +1407,28 @@ mod tests {
     use super::*;
     use blstrs::Scalar as Fr;
     use ff::Field;
+    use pasta_curves::Fp;
+    use proptest::prelude::any;
+    use proptest::{prop_assert, prop_assert_eq};
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
 
     #[test]
     fn test_minor() {
-        let one = Fr::from(1);
-        let two = Fr::from(2);
-        let three = Fr::from(3);
-        let four = Fr::from(4);
-        let five = Fr::from(5);
-        let six = Fr::from(6);
-        let seven = Fr::from(7);
-        let eight = Fr::from(8);
-        let nine = Fr::from(9);
+        test_minor_aux::<Fr>();
+        test_minor_aux::<Fp>();
+    }
+
+    fn test_minor_aux<F: PrimeField>() {
+        let one = F::from(1);
+        let two = F::from(2);
+        let three = F::from(3);
+        let four = F::from(4);
+        let five = F::from(5);
+        let six = F::from(6);
+        let seven = F::from(7);
+        let eight = F::from(8);
+        let nine = F::from(9);
 
         let m = vec![
             vec![one, two, three],
@@ -398,6 +1454,124 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_determinant() {
+        test_determinant_aux::<Fr>();
+        test_determinant_aux::<Fp>();
+    }
+
+    fn test_determinant_aux<F: PrimeField>() {
+        let zero = F::from(0);
+        let one = F::from(1);
+        let two = F::from(2);
+        let three = F::from(3);
+        let four = F::from(4);
+        let five = F::from(5);
+        let six = F::from(6);
+        let seven = F::from(7);
+        let eight = F::from(8);
+        let nine = F::from(9);
+
+        let m = vec![
+            vec![one, two, three],
+            vec![four, three, six],
+            vec![five, eight, seven],
+        ];
+        assert_eq!(F::from(28), determinant(&m));
+
+        let m1 = vec![
+            vec![one, two, three],
+            vec![four, five, six],
+            vec![seven, eight, nine],
+        ];
+        assert_eq!(zero, determinant(&m1));
+
+        // Swapping two rows negates the determinant.
+        let swapped = vec![vec![zero, one], vec![one, zero]];
+        assert_eq!(zero - one, determinant(&swapped));
+        assert_eq!(one, determinant(&make_identity::<F>(4)));
+    }
+
+    #[test]
+    fn test_adjugate() {
+        for size in 1..5 {
+            let m = random_matrix::<Fr>(size, size, size as u64);
+            let det_identity = scalar_mul(determinant(&m), &make_identity(size));
+            assert_eq!(det_identity, mat_mul(&m, &adjugate(&m)).unwrap());
+            assert_eq!(det_identity, mat_mul(&adjugate(&m), &m).unwrap());
+        }
+
+        // Singular matrices have no inverse, but the identity still holds.
+        let mut singular = random_matrix::<Fr>(3, 3, 7);
+        singular[2] = singular[0].clone();
+        assert_eq!(
+            vec![vec![Fr::zero(); 3]; 3],
+            mat_mul(&singular, &adjugate(&singular)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_leading_principal_minors() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(2), Fr::from(4), Fr::from(5)],
+            vec![Fr::from(1), Fr::from(1), Fr::from(1)],
+        ];
+        let minors = leading_principal_minors(&m);
+        assert_eq!(vec![Fr::one(), Fr::zero(), -Fr::one()], minors);
+        assert_eq!(determinant(&m), minors[2]);
+
+        // The zero leading minor forces a row swap, although `m` is invertible.
+        let (_, _, swaps) = lu_with_parity(&m).unwrap();
+        assert!(swaps > 0);
+
+        let mds = crate::mds::mds_default::<Fr>(4);
+        assert!(leading_principal_minors(&mds)
+            .iter()
+            .all(|minor| *minor != Fr::zero()));
+        assert_eq!(0, lu_with_parity(&mds).unwrap().2);
+    }
+
+    #[test]
+    fn test_lu_with_parity() {
+        test_lu_with_parity_aux::<Fr>();
+        test_lu_with_parity_aux::<Fp>();
+    }
+
+    fn test_lu_with_parity_aux<F: PrimeField>() {
+        let zero = F::zero();
+        let one = F::one();
+
+        // The first column needs a single swap, and no other column needs one.
+        let m = vec![
+            vec![zero, one, F::from(2)],
+            vec![one, zero, F::from(3)],
+            vec![F::from(4), -F::from(3), F::from(8)],
+        ];
+        let (l, u, swaps) = lu_with_parity(&m).unwrap();
+        assert_eq!(1, swaps);
+
+        for i in 0..3 {
+            assert_eq!(one, l[i][i]);
+            for j in i + 1..3 {
+                assert_eq!(zero, l[i][j]);
+                assert_eq!(zero, u[j][i]);
+            }
+        }
+        assert!(equal_up_to_row_permutation(&mat_mul(&l, &u).unwrap(), &m));
+
+        assert_eq!(-F::from(2), determinant(&m));
+        assert_eq!(determinant(&m), determinant_via_elimination(&m));
+
+        let singular = vec![
+            vec![one, F::from(2), F::from(3)],
+            vec![F::from(4), F::from(5), F::from(6)],
+            vec![F::from(7), F::from(8), F::from(9)],
+        ];
+        assert_eq!(None, lu_with_parity(&singular));
+        assert_eq!(zero, determinant_via_elimination(&singular));
+    }
+
     #[test]
     fn test_scalar_mul() {
         let zero = Fr::from(0);
@@ -415,6 +1589,69 @@ mod tests {
         assert_eq!(expected, res);
     }
 
+    #[test]
+    fn test_scale_matrix() {
+        let zero = Fr::from(0);
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let six = Fr::from(6);
+
+        let m = vec![vec![zero, one], vec![two, three]];
+        let res = scale_matrix(two, &m);
+
+        let expected = vec![vec![zero, two], vec![four, six]];
+
+        assert_eq!(expected, res);
+        assert_eq!(scalar_mul(two, &m), res);
+    }
+
+    #[test]
+    fn test_scale_vec() {
+        let v: Vec<Fr> = (1..=3).map(Fr::from).collect();
+        let expected: Vec<Fr> = [2, 4, 6].iter().copied().map(Fr::from).collect();
+
+        assert_eq!(expected, scale_vec(Fr::from(2), &v));
+        assert_eq!(vec![Fr::zero(); 3], scale_vec(Fr::zero(), &v));
+        assert!(scale_vec(Fr::from(2), &[]).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "not a matrix")]
+    fn test_scale_matrix_ragged() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+
+        let ragged = vec![vec![one, two], vec![one]];
+        assert!(!validate_matrix(&ragged));
+        scale_matrix(two, &ragged);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "not a matrix")]
+    fn test_mat_mul_ragged() {
+        let ragged = vec![vec![Fr::one(), Fr::one()], vec![Fr::one()]];
+        mat_mul(&make_identity(2), &ragged);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "not a matrix")]
+    fn test_invert_ragged() {
+        let ragged = vec![vec![Fr::one(), Fr::one()], vec![Fr::one()]];
+        invert(&ragged);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "not a matrix")]
+    fn test_apply_matrix_ragged() {
+        let ragged = vec![vec![Fr::one(), Fr::one()], vec![Fr::one()]];
+        apply_matrix(&ragged, &[Fr::one(), Fr::one()]);
+    }
+
     #[test]
     fn test_vec_mul() {
         let one = Fr::from(1);
@@ -434,7 +1671,81 @@ mod tests {
     }
 
     #[test]
-    fn test_transpose() {
+    fn test_frobenius_inner() {
+        let a: Matrix<Fr> = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(5), Fr::from(6)],
+        ];
+        let b: Matrix<Fr> = vec![
+            vec![Fr::from(6), Fr::from(5), Fr::from(4)],
+            vec![Fr::from(3), Fr::from(2), Fr::from(1)],
+        ];
+        // 6 + 10 + 12 + 12 + 10 + 6
+        assert_eq!(Some(Fr::from(56)), frobenius_inner(&a, &b));
+        assert_eq!(Some(Fr::from(91)), frobenius_inner(&a, &a));
+
+        assert_eq!(None, frobenius_inner(&a, &transpose(&b)));
+        assert_eq!(None, frobenius_inner(&a, &b[..1].to_vec()));
+    }
+
+    #[test]
+    fn test_quadratic_form() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2)],
+            vec![Fr::from(3), Fr::from(4)],
+        ];
+        let v = vec![Fr::from(5), Fr::from(6)];
+
+        // 5·(1·5 + 2·6) + 6·(3·5 + 4·6)
+        assert_eq!(Fr::from(319), quadratic_form(&m, &v));
+        assert_eq!(Fr::from(61), inner_product(&v, &v));
+    }
+
+    #[test]
+    fn test_vec_mul_batched() {
+        test_vec_mul_batched_aux::<Fr>();
+        test_vec_mul_batched_aux::<Fp>();
+    }
+
+    fn test_vec_mul_batched_aux<F: PrimeField>() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        for len in [32, 31, 3] {
+            let a: Vec<F> = (0..len).map(|_| F::random(&mut rng)).collect();
+            let b: Vec<F> = (0..len).map(|_| F::random(&mut rng)).collect();
+            let serial = a.iter().zip(&b).fold(F::zero(), |acc, (a, b)| acc + *a * b);
+
+            assert_eq!(serial, vec_mul_batched(&a, &b));
+            assert_eq!(serial, vec_mul(&a, &b));
+        }
+    }
+
+    #[test]
+    fn test_vec_add_sub_checked() {
+        let a = vec![Fr::from(1), Fr::from(2), Fr::from(3)];
+        let b = vec![Fr::from(4), Fr::from(5)];
+
+        assert!(matches!(
+            vec_add_checked(&a, &b),
+            Err(Error::InputLengthMismatch {
+                expected: 3,
+                got: 2
+            })
+        ));
+        assert!(matches!(
+            vec_sub_checked(&b, &a),
+            Err(Error::InputLengthMismatch {
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        assert_eq!(vec_add(&a, &a), vec_add_checked(&a, &a).unwrap());
+        assert_eq!(vec![Fr::zero(); 3], vec_sub_checked(&a, &a).unwrap());
+    }
+
+    #[test]
+    fn test_hadamard_mat_mul() {
         let one = Fr::from(1);
         let two = Fr::from(2);
         let three = Fr::from(3);
@@ -443,26 +1754,291 @@ mod tests {
         let six = Fr::from(6);
         let seven = Fr::from(7);
         let eight = Fr::from(8);
-        let nine = Fr::from(9);
 
-        let m = vec![
-            vec![one, two, three],
-            vec![four, five, six],
-            vec![seven, eight, nine],
-        ];
+        let a = vec![vec![one, two], vec![three, four]];
+        let b = vec![vec![five, six], vec![seven, eight]];
 
         let expected = vec![
-            vec![one, four, seven],
-            vec![two, five, eight],
-            vec![three, six, nine],
+            vec![Fr::from(5), Fr::from(12)],
+            vec![Fr::from(21), Fr::from(32)],
         ];
+        assert_eq!(Some(expected), hadamard_mat_mul(&a, &b));
 
-        let res = transpose(&m);
-        assert_eq!(expected, res);
+        let c = vec![vec![one, two, three], vec![four, five, six]];
+        assert_eq!(None, hadamard_mat_mul(&a, &c));
     }
 
     #[test]
-    fn test_inverse() {
+    fn test_apply_matrix_symbolic() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let five = Fr::from(5);
+        let six = Fr::from(6);
+        let seven = Fr::from(7);
+        let eight = Fr::from(8);
+
+        let m = vec![
+            vec![one, two, three],
+            vec![four, three, six],
+            vec![five, eight, seven],
+        ];
+        let v = vec![six, five, four];
+
+        let res = apply_matrix_symbolic(&m, &v, |m_val, v_val| *m_val * v_val);
+        assert_eq!(apply_matrix(&m, &v), res);
+    }
+
+    #[test]
+    fn test_reference_mat_vec() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2)],
+            vec![Fr::from(3), Fr::from(4)],
+        ];
+        let v = vec![Fr::from(5), Fr::from(6)];
+        assert_eq!(vec![Fr::from(17), Fr::from(39)], reference_mat_vec(&m, &v));
+
+        // `left_apply_matrix` is `M·v`, while `apply_matrix` is `v·M = Mᵀ·v`.
+        for size in 1..=6 {
+            let m: Matrix<Fr> = random_matrix(size, size, size as u64);
+            let v = random_matrix::<Fr>(1, size, 100 + size as u64).remove(0);
+            assert_eq!(reference_mat_vec(&m, &v), left_apply_matrix(&m, &v));
+            assert_eq!(reference_mat_vec(&transpose(&m), &v), apply_matrix(&m, &v));
+        }
+    }
+
+    #[test]
+    fn test_check_apply_transpose_relation() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(3), Fr::from(6)],
+            vec![Fr::from(5), Fr::from(8), Fr::from(7)],
+        ];
+        let v = vec![Fr::from(6), Fr::from(5), Fr::from(4)];
+
+        assert_ne!(m, transpose(&m));
+        assert!(check_apply_transpose_relation(&m, &v));
+        // The direction matters for a non-symmetric matrix.
+        assert_ne!(apply_matrix(&m, &v), left_apply_matrix(&m, &v));
+
+        for size in 1..=6 {
+            let m: Matrix<Fr> = random_matrix(size, size, size as u64);
+            let v = random_matrix::<Fr>(1, size, 100 + size as u64).remove(0);
+            assert!(check_apply_transpose_relation(&m, &v));
+        }
+    }
+
+    #[test]
+    fn test_apply_matrix_into() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(3), Fr::from(6)],
+            vec![Fr::from(5), Fr::from(8), Fr::from(7)],
+        ];
+        let v = vec![Fr::from(6), Fr::from(5), Fr::from(4)];
+
+        // `out` is overwritten, not accumulated into.
+        let mut out = vec![Fr::from(9); 3];
+        apply_matrix_into(&m, &v, &mut out);
+        assert_eq!(apply_matrix(&m, &v), out);
+
+        // Reuse the same two buffers across repeated applications.
+        let mut state = v.clone();
+        let mut buffer = vec![Fr::zero(); 3];
+        for _ in 0..10 {
+            apply_matrix_into(&m, &state, &mut buffer);
+            core::mem::swap(&mut state, &mut buffer);
+        }
+        assert_eq!(apply_matrix_iterated(&m, &v, 10), state);
+    }
+
+    #[test]
+    fn test_is_involution() {
+        let (zero, one) = (Fr::zero(), Fr::one());
+        assert!(is_involution(&vec![vec![zero, one], vec![one, zero]]));
+        assert!(is_involution(&vec![vec![one, zero], vec![zero, -one]]));
+        assert!(is_involution(&make_identity::<Fr>(3)));
+        assert!(!is_involution(&crate::mds::mds_default::<Fr>(3)));
+        assert!(!is_involution(&vec![vec![one, one]]));
+    }
+
+    #[test]
+    fn test_is_identity_quick() {
+        let identity = make_identity::<Fr>(4);
+        let v: Vec<Fr> = (1..=4).map(Fr::from).collect();
+        let shortcuts = || IDENTITY_SHORTCUTS.with(|count| count.get());
+
+        assert!(is_identity_quick(&identity));
+        let before = shortcuts();
+        assert_eq!(v, apply_matrix(&identity, &v));
+        assert_eq!(before + 1, shortcuts());
+        assert_eq!(v, left_apply_matrix(&identity, &v));
+        assert_eq!(v, apply_matrix_iterated(&identity, &v, 1000));
+        assert_eq!(v, apply_matrix_iterated(&matrix_power(&identity, 0), &v, 3));
+
+        let mut m = identity.clone();
+        m[3][0] = Fr::from(2);
+        assert!(!is_identity_quick(&m));
+        assert!(!is_identity(&m));
+        let before = shortcuts();
+        assert_eq!(
+            vec![v[0] + v[3] * Fr::from(2), v[1], v[2], v[3]],
+            apply_matrix(&m, &v)
+        );
+        assert_eq!(before, shortcuts());
+
+        assert!(!is_identity_quick(&random_matrix::<Fr>(4, 4, 1)));
+        assert!(!is_identity_quick(&vec![vec![Fr::one(), Fr::zero()]]));
+        assert!(!is_identity_quick(&vec![
+            vec![Fr::one(), Fr::zero()],
+            vec![Fr::zero()]
+        ]));
+        assert!(is_identity_quick::<Fr>(&Vec::new()));
+    }
+
+    #[test]
+    fn test_apply_matrix_validated() {
+        let m = random_matrix::<Fr>(3, 3, 1);
+        let v = random_matrix::<Fr>(1, 3, 2).remove(0);
+        let m_repr: Matrix<_> = m
+            .iter()
+            .map(|row| row.iter().map(Fr::to_repr).collect())
+            .collect();
+        let v_repr: Vec<_> = v.iter().map(Fr::to_repr).collect();
+        assert_eq!(
+            Ok(apply_matrix(&m, &v)),
+            apply_matrix_validated::<Fr>(&m_repr, &v_repr)
+        );
+
+        // All ones exceeds the modulus, so is not a canonical representation.
+        let mut non_canonical = v_repr.clone();
+        non_canonical[1]
+            .as_mut()
+            .iter_mut()
+            .for_each(|byte| *byte = 0xff);
+        assert_eq!(
+            Err(MatrixError::NonCanonical),
+            apply_matrix_validated::<Fr>(&m_repr, &non_canonical)
+        );
+        let mut m_non_canonical = m_repr.clone();
+        m_non_canonical[2][0] = non_canonical[1];
+        assert_eq!(
+            Err(MatrixError::NonCanonical),
+            apply_matrix_validated::<Fr>(&m_non_canonical, &v_repr)
+        );
+
+        assert_eq!(
+            Err(MatrixError::SizeMismatch {
+                expected: 3,
+                got: 2
+            }),
+            apply_matrix_validated::<Fr>(&m_repr, &v_repr[..2])
+        );
+    }
+
+    #[test]
+    fn test_try_apply_matrix() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2)],
+            vec![Fr::from(3), Fr::from(4)],
+        ];
+        let v = [Fr::from(5), Fr::from(6)];
+        assert_eq!(Ok(apply_matrix(&m, &v)), try_apply_matrix(&m, &v));
+
+        assert_eq!(
+            Err(MatrixError::SizeMismatch {
+                expected: 2,
+                got: 3
+            }),
+            try_apply_matrix(&m, &[Fr::one(); 3])
+        );
+
+        let non_square = vec![vec![Fr::one(); 3]; 2];
+        assert_eq!(
+            Err(MatrixError::NotSquare),
+            try_apply_matrix(&non_square, &v)
+        );
+        let ragged = vec![vec![Fr::one(); 2], vec![Fr::one()]];
+        assert_eq!(Err(MatrixError::NotSquare), try_apply_matrix(&ragged, &v));
+    }
+
+    #[test]
+    fn test_apply_matrix_strided() {
+        let m = random_matrix::<Fr>(3, 3, 1);
+        let vectors = random_matrix::<Fr>(5, 3, 2);
+
+        // With one state per row, the interleaved layout is column-major.
+        let states = to_column_major(&vectors);
+        assert_eq!(vectors[2][1], states[5 + 2]);
+
+        let mut out = vec![Fr::zero(); states.len()];
+        apply_matrix_strided(&m, &states, 5, &mut out);
+
+        let expected: Matrix<Fr> = vectors.iter().map(|v| apply_matrix(&m, v)).collect();
+        assert_eq!(to_column_major(&expected), out);
+
+        apply_matrix_strided(&m, &[], 0, &mut []);
+    }
+
+    #[test]
+    #[should_panic(expected = "States must each be the same size as matrix.")]
+    fn test_apply_matrix_strided_wrong_size() {
+        let m = random_matrix::<Fr>(3, 3, 1);
+        apply_matrix_strided(&m, &[Fr::one(); 8], 3, &mut [Fr::zero(); 8]);
+    }
+
+    #[test]
+    fn test_apply_matrix_add() {
+        let m = random_matrix::<Fr>(4, 4, 1);
+        let v: Vec<Fr> = (1..=4).map(Fr::from).collect();
+        let rc: Vec<Fr> = (5..=8).map(Fr::from).collect();
+
+        assert_eq!(
+            vec_add(&apply_matrix(&m, &v), &rc),
+            apply_matrix_add(&m, &v, &rc)
+        );
+        assert_eq!(
+            apply_matrix(&m, &v),
+            apply_matrix_add(&m, &v, &[Fr::zero(); 4])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Output must be the same size as vector.")]
+    fn test_apply_matrix_add_wrong_constants() {
+        let m = random_matrix::<Fr>(3, 3, 1);
+        apply_matrix_add(&m, &[Fr::one(); 3], &[Fr::one(); 2]);
+    }
+
+    #[test]
+    fn test_apply_matrix_iterated() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let five = Fr::from(5);
+        let six = Fr::from(6);
+        let seven = Fr::from(7);
+        let eight = Fr::from(8);
+
+        let m = vec![
+            vec![one, two, three],
+            vec![four, three, six],
+            vec![five, eight, seven],
+        ];
+        let v = vec![six, five, four];
+
+        assert_eq!(v, apply_matrix_iterated(&m, &v, 0));
+        assert_eq!(apply_matrix(&m, &v), apply_matrix_iterated(&m, &v, 1));
+        assert_eq!(
+            apply_matrix(&matrix_power(&m, 3), &v),
+            apply_matrix_iterated(&m, &v, 3)
+        );
+    }
+
+    #[test]
+    fn test_characteristic_poly() {
         let zero = Fr::from(0);
         let one = Fr::from(1);
         let two = Fr::from(2);
@@ -472,8 +2048,237 @@ mod tests {
         let six = Fr::from(6);
         let seven = Fr::from(7);
         let eight = Fr::from(8);
+
+        let m = vec![vec![one, two], vec![three, four]];
+        // det = 1·4 - 2·3 = -2, trace = 5.
+        let det = zero - two;
+        let expected = vec![det, -trace(&m), one];
+        assert_eq!(expected, characteristic_poly(&m));
+
+        // Cayley–Hamilton: every matrix satisfies its own characteristic polynomial.
+        let m = vec![
+            vec![one, two, three],
+            vec![four, three, six],
+            vec![five, eight, seven],
+        ];
+        let coeffs = characteristic_poly(&m);
+        assert_eq!(4, coeffs.len());
+
+        let sum = coeffs
+            .iter()
+            .enumerate()
+            .map(|(i, c)| scalar_mul(*c, &matrix_power(&m, i)))
+            .fold(vec![vec![zero; 3]; 3], |acc, term| {
+                acc.iter()
+                    .zip(term.iter())
+                    .map(|(a, b)| vec_add(a, b))
+                    .collect()
+            });
+        assert_eq!(vec![vec![zero; 3]; 3], sum);
+    }
+
+    #[test]
+    fn test_apply_matrix_rowmajor() {
+        for size in [1, 3, 16] {
+            let m = random_matrix::<Fr>(size, size, size as u64);
+            let v = random_matrix::<Fr>(1, size, 100 + size as u64).remove(0);
+            assert_eq!(apply_matrix(&m, &v), apply_matrix_rowmajor(&m, &v));
+            assert_eq!(v, apply_matrix_rowmajor(&make_identity(size), &v));
+        }
+    }
+
+    #[test]
+    fn test_count_differing_entries() {
+        let m = random_matrix::<Fr>(3, 4, 1);
+        assert_eq!(Some(0), count_differing_entries(&m, &m.clone()));
+
+        let mut changed = m.clone();
+        changed[2][1] += Fr::one();
+        assert_eq!(Some(1), count_differing_entries(&m, &changed));
+
+        assert_eq!(None, count_differing_entries(&m, &transpose(&m)));
+        assert_eq!(None, count_differing_entries(&m, &m[..2].to_vec()));
+
+        let weighted = zip_reduce(&m, &changed, 0, |acc, x, y| acc + usize::from(x != y) * 10);
+        assert_eq!(Some(10), weighted);
+    }
+
+    #[test]
+    fn test_sum_entries() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2)],
+            vec![Fr::from(3), Fr::from(4)],
+        ];
+        assert_eq!(Fr::from(10), sum_entries(&m));
+        assert_eq!(Fr::zero(), sum_entries(&vec![vec![Fr::zero(); 3]; 3]));
+        assert_eq!(Fr::zero(), sum_entries::<Fr>(&vec![]));
+    }
+
+    #[test]
+    fn test_row_sums() {
+        assert_eq!(vec![Fr::one(); 4], row_sums(&make_identity::<Fr>(4)));
+
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(3), Fr::from(6)],
+        ];
+        assert_eq!(vec![Fr::from(6), Fr::from(13)], row_sums(&m));
+
+        let non_zero = reduce_rows(&m, 0, |n, val| if *val == Fr::zero() { n } else { n + 1 });
+        assert_eq!(vec![3, 3], non_zero);
+    }
+
+    #[test]
+    fn test_format_matrix() {
+        assert_eq!(
+            "[0x1, 0x0, 0x0]\n[0x0, 0x1, 0x0]\n[0x0, 0x0, 0x1]\n",
+            format_matrix(&make_identity::<Fr>(3))
+        );
+        assert_eq!(
+            "[0x1234, 0xff]\n",
+            format_matrix(&vec![vec![Fr::from(0x1234), Fr::from(0xff)]])
+        );
+    }
+
+    #[test]
+    fn test_mat_debug() {
+        let identity = Mat(make_identity::<Fr>(3));
+        let debug = format!("{:?}", identity);
+        assert!(debug.contains("3x3"));
+        assert!(debug.contains("[0x1, 0x0, 0x0]"));
+
+        // Functions on `Matrix` accept a `Mat` through `Deref`.
+        assert!(is_identity(&identity));
+        assert_eq!(3, rows(&identity));
+
+        assert!(format!("{:?}", Mat(vec![vec![Fr::one(); 4]; 2])).starts_with("2x4 matrix\n"));
+    }
+
+    #[test]
+    fn test_parse_element() {
+        for x in [Fr::zero(), Fr::one(), Fr::from(0x1234), -Fr::one()] {
+            assert_eq!(Some(x), parse_element(&format_element(&x)));
+        }
+        assert_eq!(Some(Fr::from(0xff)), parse_element("FF"));
+        assert_eq!("0x1234", format_element(&Fr::from(0x1234)));
+
+        // The hex is of the integer value, whatever the byte order of the field's representation.
+        let x = pasta_curves::Fp::from(0x1234);
+        assert_eq!("0x1234", format_element(&x));
+        assert_eq!(Some(x), parse_element("0x1234"));
+        assert_eq!(None, parse_element::<Fr>("0x"));
+        assert_eq!(None, parse_element::<Fr>("0xfg"));
+        // The modulus itself is not canonical.
+        assert_eq!(
+            None,
+            parse_element::<Fr>(
+                "0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001"
+            )
+        );
+    }
+
+    #[test]
+    fn test_transpose() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let five = Fr::from(5);
+        let six = Fr::from(6);
+        let seven = Fr::from(7);
+        let eight = Fr::from(8);
         let nine = Fr::from(9);
 
+        let m = vec![
+            vec![one, two, three],
+            vec![four, five, six],
+            vec![seven, eight, nine],
+        ];
+
+        let expected = vec![
+            vec![one, four, seven],
+            vec![two, five, eight],
+            vec![three, six, nine],
+        ];
+
+        let res = transpose(&m);
+        assert_eq!(expected, res);
+    }
+
+    #[test]
+    fn test_mat_mul_array() {
+        test_mat_mul_array_aux::<3>();
+        test_mat_mul_array_aux::<5>();
+    }
+
+    fn test_mat_mul_array_aux<const N: usize>() {
+        let a = random_matrix::<Fr>(N, N, 1);
+        let b = random_matrix::<Fr>(N, N, 2);
+        let to_array = |m: &Matrix<Fr>| -> [[Fr; N]; N] {
+            core::array::from_fn(|i| core::array::from_fn(|j| m[i][j]))
+        };
+
+        let expected = to_array(&mat_mul(&a, &b).unwrap());
+        assert_eq!(expected, mat_mul_array(&to_array(&a), &to_array(&b)));
+    }
+
+    #[test]
+    fn test_mat_mul_chain() {
+        let a = random_matrix::<Fr>(2, 3, 1);
+        let b = random_matrix::<Fr>(3, 4, 2);
+        let c = random_matrix::<Fr>(4, 2, 3);
+
+        let expected = mat_mul(&mat_mul(&a, &b).unwrap(), &c).unwrap();
+        assert_eq!(
+            Some(expected),
+            mat_mul_chain(&[a.clone(), b.clone(), c.clone()])
+        );
+        assert_eq!(Some(a.clone()), mat_mul_chain(core::slice::from_ref(&a)));
+
+        // Incompatible dimensions are rejected, wherever they occur in the chain.
+        assert_eq!(None, mat_mul_chain(&[a.clone(), b.clone(), b.clone()]));
+        assert_eq!(None, mat_mul_chain(&[b, a, c]));
+        assert_eq!(None, mat_mul_chain::<Fr>(&[]));
+    }
+
+    #[test]
+    fn test_column_major() {
+        let m: Matrix<Fr> = (0..2)
+            .map(|i| (0..3).map(|j| Fr::from(3 * i + j + 1)).collect())
+            .collect();
+        let expected: Vec<Fr> = [1, 4, 2, 5, 3, 6].iter().map(|n| Fr::from(*n)).collect();
+
+        let data = to_column_major(&m);
+        assert_eq!(expected, data);
+        assert_eq!(Some(m), from_column_major(&data, 2, 3));
+        assert_eq!(None, from_column_major(&data, 3, 3));
+
+        let square = random_matrix::<Fr>(4, 4, 1);
+        assert_eq!(to_column_major(&square), transpose(&square).concat());
+        assert_eq!(
+            Some(square.clone()),
+            from_column_major(&to_column_major(&square), 4, 4)
+        );
+    }
+
+    #[test]
+    fn test_inverse() {
+        test_inverse_aux::<Fr>();
+        test_inverse_aux::<Fp>();
+    }
+
+    fn test_inverse_aux<F: PrimeField>() {
+        let zero = F::from(0);
+        let one = F::from(1);
+        let two = F::from(2);
+        let three = F::from(3);
+        let four = F::from(4);
+        let five = F::from(5);
+        let six = F::from(6);
+        let seven = F::from(7);
+        let eight = F::from(8);
+        let nine = F::from(9);
+
         let m = vec![
             vec![one, two, three],
             vec![four, three, six],
@@ -493,6 +2298,8 @@ mod tests {
 
         let computed_identity = mat_mul(&m, &m_inv).unwrap();
         assert!(is_identity(&computed_identity));
+        assert!(check_double_inverse(&m));
+        assert!(!check_double_inverse(&m1));
 
         // S
         let some_vec = vec![six, five, four];
@@ -530,6 +2337,438 @@ mod tests {
         assert!(is_identity(&computed_identity));
     }
 
+    #[test]
+    fn test_is_invertible_wide() {
+        // The cofactor `determinant` would need 9! terms here.
+        let mut m = crate::mds::mds_default::<Fr>(9);
+        assert!(is_invertible(&m));
+        assert_ne!(Fr::zero(), determinant_via_elimination(&m));
+
+        m[8] = m[0].clone();
+        assert!(!is_invertible(&m));
+        assert!(!is_invertible(&vec![vec![Fr::one(); 2]; 3]));
+    }
+
+    #[test]
+    fn test_invert_diagonal() {
+        let diag: Vec<Fr> = (2..6).map(Fr::from).collect();
+        let m = diagonal_matrix(&diag);
+        let inverse = invert_diagonal(&diag).unwrap();
+
+        assert!(is_identity(&mat_mul(&m, &inverse).unwrap()));
+        assert_eq!(invert(&m), Some(inverse));
+
+        let mut singular = diag;
+        singular[2] = Fr::zero();
+        assert_eq!(None, invert_diagonal(&singular));
+    }
+
+    #[test]
+    fn test_invert_checked() {
+        test_invert_checked_aux::<Fr>();
+        test_invert_checked_aux::<Fp>();
+    }
+
+    fn test_invert_checked_aux<F: PrimeField>() {
+        let zero = F::from(0);
+        let one = F::from(1);
+
+        let invertible = [
+            vec![
+                vec![F::from(1), F::from(2), F::from(3)],
+                vec![F::from(4), F::from(3), F::from(6)],
+                vec![F::from(5), F::from(8), F::from(7)],
+            ],
+            vec![vec![zero, one], vec![one, zero]],
+            vec![
+                vec![zero, F::from(2), one],
+                vec![F::from(3), zero, zero],
+                vec![one, one, zero],
+            ],
+        ];
+        for m in invertible.iter() {
+            assert_eq!(invert(m), invert_checked(m));
+            assert!(is_identity(
+                &mat_mul(m, &invert_checked(m).unwrap()).unwrap()
+            ));
+        }
+
+        let singular = [
+            vec![
+                vec![F::from(1), F::from(2), F::from(3)],
+                vec![F::from(4), F::from(5), F::from(6)],
+                vec![F::from(7), F::from(8), F::from(9)],
+            ],
+            vec![vec![zero, zero], vec![one, zero]],
+        ];
+        for m in singular.iter() {
+            assert_eq!(None, invert_checked(m));
+            assert!(!is_invertible(m));
+        }
+
+        let not_square = vec![vec![one, zero, zero], vec![zero, one, zero]];
+        assert_eq!(None, invert_checked(&not_square));
+    }
+
+    #[test]
+    fn test_random_matrix() {
+        let m: Matrix<Fr> = random_matrix(3, 4, 1);
+        assert_eq!(3, rows(&m));
+        assert_eq!(4, columns(&m));
+        assert_eq!(m, random_matrix(3, 4, 1));
+        assert_ne!(m, random_matrix(3, 4, 2));
+
+        for size in 1..=8 {
+            for seed in 0..8 {
+                assert!(is_invertible(&random_matrix::<Fr>(size, size, seed)));
+            }
+        }
+    }
+
+    /// Runs `test` on pseudo-random inputs from `strategy`, from a fixed seed so failures are reproducible.
+    fn run_property<S, T>(strategy: S, test: T)
+    where
+        S: proptest::strategy::Strategy,
+        S::Value: std::fmt::Debug,
+        T: Fn(S::Value) -> Result<(), proptest::test_runner::TestCaseError>,
+    {
+        use proptest::test_runner::{Config, RngAlgorithm, TestRng, TestRunner};
+
+        let rng = TestRng::from_seed(RngAlgorithm::ChaCha, &[7; 32]);
+        let mut runner = TestRunner::new_with_rng(Config::default(), rng);
+        runner.run(&strategy, test).unwrap();
+    }
+
+    #[test]
+    fn prop_inverse_round_trip() {
+        run_property((1usize..=8, any::<u64>()), |(size, seed)| {
+            let m: Matrix<Fr> = random_matrix(size, size, seed);
+            if is_invertible(&m) {
+                let m_inv = invert(&m).unwrap();
+                prop_assert!(is_identity(&mat_mul(&m, &m_inv).unwrap()));
+                prop_assert!(is_identity(&mat_mul(&m_inv, &m).unwrap()));
+            }
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn prop_rectangular_mat_mul() {
+        run_property(
+            (1usize..=8, 1usize..=8, 1usize..=8, any::<u64>()),
+            |(n, k, m, seed)| {
+                let a: Matrix<Fr> = random_matrix(n, k, seed);
+                let b: Matrix<Fr> = random_matrix(k, m, seed.wrapping_add(1));
+                let ab = mat_mul(&a, &b).unwrap();
+
+                prop_assert_eq!(n, rows(&ab));
+                prop_assert_eq!(m, columns(&ab));
+                // (AB)ᵀ = BᵀAᵀ
+                prop_assert_eq!(
+                    transpose(&ab),
+                    mat_mul(&transpose(&b), &transpose(&a)).unwrap()
+                );
+                prop_assert_eq!(m == n, mat_mul(&b, &a).is_some());
+                Ok(())
+            },
+        );
+    }
+
+    #[test]
+    fn test_invert_in_place() {
+        let m: Matrix<Fr> = random_matrix(5, 5, 0);
+
+        let inverse = invert_in_place(m.clone()).unwrap();
+        assert_eq!(invert(&m).unwrap(), inverse);
+        assert!(is_identity(&mat_mul(&m, &inverse).unwrap()));
+        assert!(is_identity(&mat_mul(&inverse, &m).unwrap()));
+
+        let mut singular = m;
+        singular[4] = singular[0].clone();
+        assert_eq!(None, invert_in_place(singular));
+    }
+
+    #[test]
+    fn test_pivot_columns() {
+        // Rank 2: the second column is twice the first.
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(2), Fr::from(4), Fr::from(5)],
+            vec![Fr::from(3), Fr::from(6), Fr::from(7)],
+        ];
+        assert_eq!(vec![0, 2], pivot_columns(&m));
+
+        assert_eq!(
+            vec![0, 1, 2, 3],
+            pivot_columns(&random_matrix::<Fr>(4, 4, 1))
+        );
+        assert_eq!(vec![0, 1], pivot_columns(&random_matrix::<Fr>(2, 4, 1)));
+        assert!(pivot_columns(&vec![vec![Fr::zero(); 3]; 3]).is_empty());
+    }
+
+    #[test]
+    fn test_pseudo_inverse() {
+        let m = random_matrix::<Fr>(4, 4, 3);
+        assert_eq!(invert(&m), Some(pseudo_inverse(&m)));
+
+        let is_generalized_inverse = |a: &Matrix<Fr>, g: &Matrix<Fr>| {
+            let aga = mat_mul(&mat_mul(a, g).unwrap(), a).unwrap();
+            let gag = mat_mul(&mat_mul(g, a).unwrap(), g).unwrap();
+            aga == *a && gag == *g
+        };
+
+        // Rank 2: the third row is the sum of the first two.
+        let mut singular = random_matrix::<Fr>(3, 3, 4);
+        singular[2] = vec_add(&singular[0], &singular[1]);
+        let g = pseudo_inverse(&singular);
+        assert!(is_generalized_inverse(&singular, &g));
+        assert!(!is_invertible(&g));
+
+        let wide = random_matrix::<Fr>(2, 3, 5);
+        let g = pseudo_inverse(&wide);
+        assert_eq!((3, 2), (rows(&g), columns(&g)));
+        assert!(is_generalized_inverse(&wide, &g));
+        assert!(is_generalized_inverse(
+            &transpose(&wide),
+            &pseudo_inverse(&transpose(&wide))
+        ));
+
+        let zero = vec![vec![Fr::zero(); 3]; 2];
+        assert_eq!(vec![vec![Fr::zero(); 2]; 3], pseudo_inverse(&zero));
+    }
+
+    #[test]
+    fn test_solve_linear_system() {
+        let a = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(3), Fr::from(6)],
+            vec![Fr::from(5), Fr::from(8), Fr::from(7)],
+        ];
+        let x = vec![Fr::from(6), Fr::from(5), Fr::from(4)];
+        let b = left_apply_matrix(&a, &x);
+
+        assert_eq!(Some(x), solve_linear_system(&a, &b));
+        assert_eq!(None, solve_linear_system(&a, &b[..2]));
+
+        let singular = vec![vec![Fr::one(), Fr::one()], vec![Fr::one(), Fr::one()]];
+        assert_eq!(
+            None,
+            solve_linear_system(&singular, &[Fr::one(), Fr::zero()])
+        );
+    }
+
+    #[test]
+    fn test_inverse_column() {
+        let m: Matrix<Fr> = random_matrix(5, 5, 0);
+        let columns: Matrix<Fr> = (0..5).map(|j| inverse_column(&m, j).unwrap()).collect();
+
+        assert_eq!(invert(&m).unwrap(), transpose(&columns));
+
+        let singular = vec![vec![Fr::one(), Fr::one()], vec![Fr::one(), Fr::one()]];
+        assert_eq!(None, inverse_column(&singular, 0));
+    }
+
+    #[test]
+    fn test_apply_inverse() {
+        for size in 1..=6 {
+            let m: Matrix<Fr> = random_matrix(size, size, size as u64);
+            let v = random_matrix::<Fr>(1, size, 100 + size as u64).remove(0);
+
+            assert_eq!(
+                Some(apply_matrix(&invert(&m).unwrap(), &v)),
+                apply_inverse(&m, &v)
+            );
+        }
+    }
+
+    #[test]
+    fn test_invert_block() {
+        let m: Matrix<Fr> = random_matrix(6, 6, 0);
+
+        let expected = invert(&m).unwrap();
+        assert_eq!(Some(expected.clone()), invert_block(&m, 3));
+        assert_eq!(Some(expected), invert_block(&m, 2));
+
+        assert_eq!(None, invert_block(&m, 0));
+        assert_eq!(None, invert_block(&m, 6));
+
+        // The leading block is singular, even though the whole matrix is invertible.
+        let zero = Fr::zero();
+        let one = Fr::one();
+        let m = vec![vec![zero, one], vec![one, zero]];
+        assert_eq!(None, invert_block(&m, 1));
+    }
+
+    #[test]
+    fn test_mat_mul_rectangular() {
+        let a = vec![vec![Fr::from(1), Fr::from(2), Fr::from(3)]];
+        let b = vec![vec![Fr::from(4)], vec![Fr::from(5)], vec![Fr::from(6)]];
+
+        assert_eq!(Some(vec![vec![Fr::from(32)]]), mat_mul(&a, &b));
+        assert_eq!(
+            vec![vec![Fr::from(4), Fr::from(5), Fr::from(6)]],
+            transpose(&b)
+        );
+        assert_eq!(None, mat_mul(&a, &a));
+    }
+
+    #[test]
+    fn test_check_affine_commute() {
+        let m = vec![
+            vec![Fr::from(1), Fr::from(2), Fr::from(3)],
+            vec![Fr::from(4), Fr::from(3), Fr::from(6)],
+            vec![Fr::from(5), Fr::from(8), Fr::from(7)],
+        ];
+        let m_inv = invert(&m).unwrap();
+        let s = vec![Fr::from(6), Fr::from(5), Fr::from(4)];
+        let b = vec![Fr::from(8), Fr::from(2), Fr::from(5)];
+
+        assert!(check_affine_commute(&m, &m_inv, &s, &b));
+
+        let mut wrong_inv = m_inv;
+        wrong_inv[1][2] += Fr::one();
+        assert!(!check_affine_commute(&m, &wrong_inv, &s, &b));
+    }
+
+    #[test]
+    fn test_inverse_and_transpose() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let five = Fr::from(5);
+        let six = Fr::from(6);
+        let seven = Fr::from(7);
+        let eight = Fr::from(8);
+
+        let m = vec![
+            vec![one, two, three],
+            vec![four, three, six],
+            vec![five, eight, seven],
+        ];
+
+        let (m_inv, m_inv_t) = inverse_and_transpose(&m).unwrap();
+        assert_eq!(invert(&m).unwrap(), m_inv);
+        assert_eq!(transpose(&invert(&m).unwrap()), m_inv_t);
+
+        let singular = vec![vec![one, two], vec![two, four]];
+        assert!(inverse_and_transpose(&singular).is_none());
+    }
+
+    #[test]
+    fn test_is_permutation_matrix() {
+        let zero = Fr::from(0);
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+
+        assert!(is_permutation_matrix(&make_identity::<Fr>(3)));
+
+        let swapped = vec![
+            vec![zero, one, zero],
+            vec![one, zero, zero],
+            vec![zero, zero, one],
+        ];
+        assert!(is_permutation_matrix(&swapped));
+
+        let scaled = vec![
+            vec![zero, two, zero],
+            vec![one, zero, zero],
+            vec![zero, zero, one],
+        ];
+        assert!(!is_permutation_matrix(&scaled));
+
+        let repeated_column = vec![
+            vec![one, zero, zero],
+            vec![one, zero, zero],
+            vec![zero, zero, one],
+        ];
+        assert!(!is_permutation_matrix(&repeated_column));
+    }
+
+    #[test]
+    fn test_permute_state() {
+        let v: Vec<Fr> = (1..=4).map(Fr::from).collect();
+
+        // Rotate left by one.
+        let rotated = permute_state(&v, &[1, 2, 3, 0]);
+        assert_eq!(vec![v[1], v[2], v[3], v[0]], rotated);
+        assert_eq!(v, permute_state(&rotated, &[3, 0, 1, 2]));
+
+        // Permuting the state is the same as applying the corresponding permutation matrix.
+        let mut p = vec![vec![Fr::zero(); 4]; 4];
+        for (i, j) in [1, 2, 3, 0].iter().enumerate() {
+            p[*j][i] = Fr::one();
+        }
+        assert!(is_permutation_matrix(&p));
+        assert_eq!(rotated, apply_matrix(&p, &v));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid permutation of state indices")]
+    fn test_permute_state_invalid() {
+        let v: Vec<Fr> = (1..=3).map(Fr::from).collect();
+        permute_state(&v, &[0, 1, 1]);
+    }
+
+    #[test]
+    fn test_equal_up_to_row_permutation() {
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+        let four = Fr::from(4);
+        let five = Fr::from(5);
+        let six = Fr::from(6);
+        let seven = Fr::from(7);
+        let eight = Fr::from(8);
+        let nine = Fr::from(9);
+
+        let m = vec![
+            vec![one, two, three],
+            vec![four, five, six],
+            vec![seven, eight, nine],
+        ];
+
+        let shuffled = vec![
+            vec![seven, eight, nine],
+            vec![one, two, three],
+            vec![four, five, six],
+        ];
+        assert!(equal_up_to_row_permutation(&m, &shuffled));
+        assert!(equal_up_to_row_permutation(&shuffled, &m));
+
+        let mut changed = shuffled;
+        changed[2][1] = nine;
+        assert!(!equal_up_to_row_permutation(&m, &changed));
+
+        let duplicated = vec![
+            vec![one, two, three],
+            vec![one, two, three],
+            vec![seven, eight, nine],
+        ];
+        assert!(!equal_up_to_row_permutation(&m, &duplicated));
+    }
+
+    #[test]
+    fn test_find_pivot() {
+        let zero = Fr::from(0);
+        let one = Fr::from(1);
+        let two = Fr::from(2);
+        let three = Fr::from(3);
+
+        let m = vec![
+            vec![zero, one, two],
+            vec![zero, zero, three],
+            vec![one, two, zero],
+        ];
+
+        assert_eq!(Some(2), find_pivot(&m, 0, 0));
+        assert_eq!(Some(2), find_pivot(&m, 0, 1));
+        assert_eq!(Some(0), find_pivot(&m, 1, 0));
+        assert_eq!(Some(2), find_pivot(&m, 1, 1));
+        assert_eq!(None, find_pivot(&m, 2, 2));
+        assert_eq!(None, find_pivot(&m, 0, 3));
+    }
+
     #[test]
     fn test_eliminate() {
         //let one = Fr::from(1);
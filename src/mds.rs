@@ -1,12 +1,14 @@
 // Allow `&Matrix` in function signatures.
 #![allow(clippy::ptr_arg)]
 
+use alloc::vec::Vec;
 use ff::PrimeField;
 use serde::{Deserialize, Serialize};
 
 use crate::matrix;
 use crate::matrix::{
-    apply_matrix, invert, is_identity, is_invertible, is_square, mat_mul, minor, transpose, Matrix,
+    apply_matrix, determinant_via_elimination, invert, is_identity, is_invertible, is_square,
+    mat_mul, matrix_power, minor, rows, submatrix, transpose, Matrix,
 };
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -27,7 +29,7 @@ pub fn create_mds_matrices<F: PrimeField>(t: usize) -> MdsMatrices<F> {
 pub fn derive_mds_matrices<F: PrimeField>(m: Matrix<F>) -> MdsMatrices<F> {
     let m_inv = invert(&m).unwrap(); // m is MDS so invertible.
     let m_hat = minor(&m, 0, 0);
-    let m_hat_inv = invert(&m_hat).unwrap(); // If this returns None, then `mds_matrix` was not correctly generated.
+    let m_hat_inv = invert_lower_right_block(&m).unwrap(); // If this returns None, then `mds_matrix` was not correctly generated.
     let m_prime = make_prime(&m);
     let m_double_prime = make_double_prime(&m, &m_hat_inv);
 
@@ -41,6 +43,17 @@ pub fn derive_mds_matrices<F: PrimeField>(m: Matrix<F>) -> MdsMatrices<F> {
     }
 }
 
+/// The inverse of the lower-right `(t-1)`×`(t-1)` block of the `t`×`t` matrix `m`, i.e. of `M^` (`m_hat`) in
+/// `derive_mds_matrices`, which the sparse factorization inverts for every power of the MDS matrix. Returns `None`
+/// if `m` is not square and non-empty, or the block is singular.
+pub fn invert_lower_right_block<F: PrimeField>(m: &Matrix<F>) -> Option<Matrix<F>> {
+    let t = rows(m);
+    if t == 0 || !is_square(m) {
+        return None;
+    }
+    invert(&submatrix(m, 1..t, 1..t))
+}
+
 /// A `SparseMatrix` is specifically one of the form of M''.
 /// This means its first row and column are each dense, and the interior matrix
 /// (minor to the element in both the row and column) is the identity.
@@ -72,6 +85,35 @@ impl<F: PrimeField> SparseMatrix<F> {
         self.w_hat.len()
     }
 
+    /// Right-multiply `v` by this matrix, as `apply_matrix` would with the equivalent dense matrix,
+    /// but in time linear in the size.
+    pub fn apply(&self, v: &[F]) -> Vec<F> {
+        assert_eq!(
+            self.size(),
+            v.len(),
+            "Matrix can only be applied to vector of same size."
+        );
+        let mut result = Vec::with_capacity(v.len());
+
+        // First column is dense.
+        let mut first = F::zero();
+        for (w, val) in self.w_hat.iter().zip(v) {
+            let mut tmp = *w;
+            tmp.mul_assign(val);
+            first.add_assign(&tmp);
+        }
+        result.push(first);
+
+        // First row is dense, and the rest of the diagonal is one.
+        for (v_rest, val) in self.v_rest.iter().zip(&v[1..]) {
+            let mut tmp = *v_rest;
+            tmp.mul_assign(&v[0]);
+            tmp.add_assign(val);
+            result.push(tmp);
+        }
+        result
+    }
+
     pub fn to_matrix(&self) -> Matrix<F> {
         let mut m = matrix::make_identity(self.size());
         for (j, elt) in self.w_hat.iter().enumerate() {
@@ -118,6 +160,49 @@ pub fn factor_to_sparse_matrices<F: PrimeField>(
     (pre_sparse, all)
 }
 
+/// Compute only the pre-sparse matrix, M', which precedes the `partial_rounds` sparse matrices in the optimized
+/// permutation. Together they satisfy M' x M''_1 x ... x M''_n = M^(n+1), where n = `partial_rounds`.
+pub fn compute_pre_sparse_matrix<F: PrimeField>(
+    mds: &Matrix<F>,
+    partial_rounds: usize,
+) -> Matrix<F> {
+    let (pre_sparse, _) = factor_to_sparse_matrices(mds.clone(), partial_rounds);
+    pre_sparse
+}
+
+/// Check that `pre_sparse` and `sparse` (as returned by `factor_to_sparse_matrixes`) multiply back to the
+/// power of `mds` they were factored from: M' x M''_1 x ... x M''_n = M^(n+1).
+pub fn verify_sparse_factorization<F: PrimeField>(
+    mds: &Matrix<F>,
+    pre_sparse: &Matrix<F>,
+    sparse: &[SparseMatrix<F>],
+) -> bool {
+    let product = sparse
+        .iter()
+        .try_fold(pre_sparse.clone(), |acc, m| mat_mul(&acc, &m.to_matrix()));
+
+    product == Some(matrix_power(mds, sparse.len() + 1))
+}
+
+/// The `t`×`t` Cauchy MDS matrix with entries `1 / (x_i + y_j)`, where `xs = [0, t)` and `ys = [t, 2t)`. This is
+/// the convention used by the Filecoin reference implementation, and is the matrix used by `create_mds_matrices`.
+pub fn mds_default<F: PrimeField>(t: usize) -> Matrix<F> {
+    generate_mds(t)
+}
+
+/// The circulant matrix whose first row is `first_row`, and each subsequent row is the previous one cyclically
+/// shifted right by one. Circulant matrices are an alternative MDS construction; check candidates with `is_mds`.
+pub fn circulant<F: PrimeField>(first_row: &[F]) -> Matrix<F> {
+    let size = first_row.len();
+    (0..size)
+        .map(|i| {
+            (0..size)
+                .map(|j| first_row[(j + size - i) % size])
+                .collect()
+        })
+        .collect()
+}
+
 fn generate_mds<F: PrimeField>(t: usize) -> Matrix<F> {
     // Source: https://github.com/dusk-network/dusk-poseidon-merkle/commit/776c37734ea2e71bb608ce4bc58fdb5f208112a7#diff-2eee9b20fb23edcc0bf84b14167cbfdc
     // Generate x and y values deterministically for the cauchy matrix
@@ -152,6 +237,159 @@ fn generate_mds<F: PrimeField>(t: usize) -> Matrix<F> {
     matrix
 }
 
+/// Returns `true` if every square submatrix of `m` is non-singular. This checks all `4^n` pairs of row and
+/// column subsets, so only use it on small matrices.
+pub fn is_mds<F: PrimeField>(m: &Matrix<F>) -> bool {
+    is_square(m) && first_singular_submatrix(m).is_none()
+}
+
+/// The row and column indices of the first singular square submatrix of `m`, smallest submatrices first, or `None`
+/// if there is none (i.e. a square `m` is MDS). Like `is_mds`, only use this on small matrices.
+pub fn first_singular_submatrix<F: PrimeField>(m: &Matrix<F>) -> Option<(Vec<usize>, Vec<usize>)> {
+    let (rows, cols) = (m.len(), m.first().map_or(0, Vec::len));
+    assert!(rows < 32 && cols < 32);
+    let indices = |mask: u32| (0..32).filter(move |i| mask & (1 << i) != 0);
+
+    (1..=rows.min(cols)).find_map(|size| {
+        let row_masks = (1..1u32 << rows).filter(move |mask| mask.count_ones() as usize == size);
+        row_masks
+            .flat_map(|row_mask| {
+                (1..1u32 << cols)
+                    .filter(move |mask| mask.count_ones() as usize == size)
+                    .map(move |col_mask| (row_mask, col_mask))
+            })
+            .find(|(row_mask, col_mask)| {
+                let submatrix = indices(*row_mask)
+                    .map(|i| indices(*col_mask).map(|j| m[i][j]).collect())
+                    .collect();
+                determinant_via_elimination(&submatrix) == F::zero()
+            })
+            .map(|(row_mask, col_mask)| (indices(row_mask).collect(), indices(col_mask).collect()))
+    })
+}
+
+/// The branch number of `m`: the minimum of `wt(x) + wt(x·m)` over non-zero `x`, where `wt` counts the non-zero
+/// entries. This is the minimum distance of the code generated by `[I | m]`, computed exactly over all row and column
+/// subsets, so only use it on small matrices. A `t × t` matrix is MDS if and only if its branch number is `t + 1`.
+pub fn branch_number<F: PrimeField>(m: &Matrix<F>) -> usize {
+    let (rows, cols) = (m.len(), m.first().map_or(0, Vec::len));
+    assert!(rows < 32 && cols < 32);
+    let indices = |mask: u32| (0..32).filter(move |i| mask & (1 << i) != 0);
+
+    // Some non-zero `x` supported on the rows in `row_mask` has `x·m` vanishing on the columns in `col_mask` exactly
+    // when that submatrix has fewer independent rows than it has rows.
+    (1..1u32 << rows)
+        .flat_map(|row_mask| (0..1u32 << cols).map(move |col_mask| (row_mask, col_mask)))
+        .filter(|(row_mask, col_mask)| {
+            let submatrix: Matrix<F> = indices(*row_mask)
+                .map(|i| indices(*col_mask).map(|j| m[i][j]).collect())
+                .collect();
+            matrix::pivot_columns(&submatrix).len() < row_mask.count_ones() as usize
+        })
+        .map(|(row_mask, col_mask)| {
+            row_mask.count_ones() as usize + cols - col_mask.count_ones() as usize
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// The matrix `basis⁻¹·m·basis` similar to `m`, i.e. `m` expressed in the basis given by the columns of `basis`. A
+/// permutation using it in place of `m` is only equivalent if its state is transformed by the same change of basis.
+/// Returns `None` if `basis` is singular or its size does not match `m`.
+pub fn equivalent_mds<F: PrimeField>(m: &Matrix<F>, basis: &Matrix<F>) -> Option<Matrix<F>> {
+    let basis_inv = matrix::invert_checked(basis)?;
+    mat_mul(&mat_mul(&basis_inv, m)?, basis)
+}
+
+/// Returns `m` with `m[i][j]` scaled by `row_scales[i] · col_scales[j]`. If all scales are non-zero, this preserves
+/// the MDS property.
+pub fn scale_rows_cols<F: PrimeField>(
+    m: &Matrix<F>,
+    row_scales: &[F],
+    col_scales: &[F],
+) -> Matrix<F> {
+    assert_eq!(m.len(), row_scales.len());
+    m.iter()
+        .zip(row_scales)
+        .map(|(row, row_scale)| {
+            assert_eq!(row.len(), col_scales.len());
+            row.iter()
+                .zip(col_scales)
+                .map(|(val, col_scale)| *val * row_scale * col_scale)
+                .collect()
+        })
+        .collect()
+}
+
+/// Widens the Cauchy matrix `base`, with entries `1 / (x_i + y_j)`, to a `new_t × new_t` Cauchy matrix whose
+/// top-left block is `base`. New points are chosen as small integers `x` paired with `y = x + (y_0 - x_0)`, so a
+/// symmetric `base` stays symmetric. Returns `None` if `base` is not a Cauchy matrix, `new_t` is smaller than its
+/// size, or the result fails `is_mds`.
+pub fn extend_mds<F: PrimeField>(base: &Matrix<F>, new_t: usize) -> Option<Matrix<F>> {
+    if !is_square(base) || base.is_empty() || new_t < base.len() {
+        return None;
+    }
+    let t = base.len();
+
+    // Cauchy points are only determined up to a shift `x_i + c, y_j - c`, so fix `x_0 = 0`.
+    let mut ys: Vec<F> = base[0]
+        .iter()
+        .map(|entry| Option::from(entry.invert()))
+        .collect::<Option<_>>()?;
+    let mut xs: Vec<F> = base
+        .iter()
+        .map(|row| Option::<F>::from(row[0].invert()).map(|x| x - ys[0]))
+        .collect::<Option<_>>()?;
+    let is_cauchy = base.iter().zip(&xs).all(|(row, x)| {
+        row.iter()
+            .zip(&ys)
+            .all(|(entry, y)| *entry * (*x + y) == F::one())
+    });
+    if !is_cauchy {
+        return None;
+    }
+
+    let shift = ys[0];
+    let mut candidate = t as u64;
+    while xs.len() < new_t {
+        let x = F::from(candidate);
+        let y = x + shift;
+        candidate += 1;
+
+        let fresh = !xs.contains(&x) && !ys.contains(&y) && x + y != F::zero();
+        if fresh
+            && xs.iter().all(|xi| *xi + y != F::zero())
+            && ys.iter().all(|yj| x + yj != F::zero())
+        {
+            xs.push(x);
+            ys.push(y);
+        }
+    }
+
+    let extended: Matrix<F> = xs
+        .iter()
+        .enumerate()
+        .map(|(i, x)| {
+            ys.iter()
+                .enumerate()
+                .map(|(j, y)| {
+                    if i < t && j < t {
+                        base[i][j]
+                    } else {
+                        (*x + y).invert().unwrap()
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    if is_mds(&extended) {
+        Some(extended)
+    } else {
+        None
+    }
+}
+
 fn make_prime<F: PrimeField>(m: &Matrix<F>) -> Matrix<F> {
     m.iter()
         .enumerate()
@@ -239,6 +477,161 @@ mod tests {
         assert_eq!(m, matrix::mat_mul(&m_prime, &m_double_prime).unwrap());
     }
 
+    #[test]
+    fn test_mds_default() {
+        let inv = |n: u64| Fr::from(n).invert().unwrap();
+        let expected = vec![
+            vec![inv(3), inv(4), inv(5)],
+            vec![inv(4), inv(5), inv(6)],
+            vec![inv(5), inv(6), inv(7)],
+        ];
+
+        assert_eq!(expected, mds_default::<Fr>(3));
+        assert_eq!(expected, create_mds_matrices::<Fr>(3).m);
+    }
+
+    #[test]
+    fn test_is_mds() {
+        assert!(is_mds(&mds_default::<Fr>(4)));
+
+        let mut m = mds_default::<Fr>(4);
+        // A zero entry is a singular 1×1 submatrix, though the matrix itself stays invertible.
+        m[0][0] = Fr::zero();
+        assert!(is_invertible(&m));
+        assert!(!is_mds(&m));
+    }
+
+    #[test]
+    fn test_first_singular_submatrix() {
+        let one = Fr::one();
+        assert_eq!(
+            Some((vec![0, 1], vec![0, 1])),
+            first_singular_submatrix(&vec![vec![one, one], vec![one, one]])
+        );
+        assert_eq!(None, first_singular_submatrix(&mds_default::<Fr>(4)));
+
+        // Smaller singular submatrices are reported first.
+        let mut m = mds_default::<Fr>(4);
+        m[2][1] = Fr::zero();
+        assert_eq!(Some((vec![2], vec![1])), first_singular_submatrix(&m));
+    }
+
+    #[test]
+    fn test_branch_number() {
+        assert_eq!(4, branch_number(&mds_default::<Fr>(3)));
+        assert_eq!(5, branch_number(&mds_default::<Fr>(4)));
+        assert_eq!(2, branch_number(&matrix::make_identity::<Fr>(3)));
+
+        // `x = (1, -1)` is sent to zero.
+        let one = Fr::one();
+        assert_eq!(2, branch_number(&vec![vec![one, one], vec![one, one]]));
+
+        // A single zero entry lets a weight-one `x` reach weight `t - 1`.
+        let mut m = mds_default::<Fr>(4);
+        m[2][1] = Fr::zero();
+        assert_eq!(4, branch_number(&m));
+    }
+
+    #[test]
+    fn test_equivalent_mds() {
+        let m = mds_default::<Fr>(4);
+        let basis = matrix::random_matrix::<Fr>(4, 4, 7);
+
+        let equivalent = equivalent_mds(&m, &basis).unwrap();
+        assert_ne!(m, equivalent);
+        assert_eq!(
+            determinant_via_elimination(&m),
+            determinant_via_elimination(&equivalent)
+        );
+        assert_eq!(matrix::trace(&m), matrix::trace(&equivalent));
+        assert_eq!(
+            matrix::characteristic_poly(&m),
+            matrix::characteristic_poly(&equivalent)
+        );
+        assert_eq!(
+            Some(m.clone()),
+            equivalent_mds(&m, &matrix::make_identity(4))
+        );
+
+        let mut singular = basis.clone();
+        singular[3] = singular[0].clone();
+        assert_eq!(None, equivalent_mds(&m, &singular));
+        assert_eq!(None, equivalent_mds(&m, &matrix::make_identity(3)));
+    }
+
+    #[test]
+    fn test_scale_rows_cols() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+        let m = mds_default::<Fr>(4);
+        let row_scales: Vec<Fr> = (0..4).map(|_| Fr::random(&mut rng)).collect();
+        let col_scales: Vec<Fr> = (0..4).map(|_| Fr::random(&mut rng)).collect();
+
+        let scaled = scale_rows_cols(&m, &row_scales, &col_scales);
+        assert_eq!(m[1][2] * row_scales[1] * col_scales[2], scaled[1][2]);
+        assert!(is_mds(&scaled));
+
+        let mut col_scales = col_scales;
+        col_scales[3] = Fr::zero();
+        assert!(!is_mds(&scale_rows_cols(&m, &row_scales, &col_scales)));
+    }
+
+    #[test]
+    fn test_invert_lower_right_block() {
+        let m = mds_default::<Fr>(4);
+        let block: Matrix<Fr> = m[1..].iter().map(|row| row[1..].to_vec()).collect();
+        let expected = invert(&block).unwrap();
+
+        assert_eq!(Some(expected.clone()), invert_lower_right_block(&m));
+        assert_eq!(expected, create_mds_matrices::<Fr>(4).m_hat_inv);
+
+        // For every power of the MDS matrix the sparse factorization goes through.
+        let m_squared = matrix::mat_mul(&m, &m).unwrap();
+        assert_eq!(
+            invert(&minor(&m_squared, 0, 0)),
+            invert_lower_right_block(&m_squared)
+        );
+
+        assert_eq!(None, invert_lower_right_block::<Fr>(&vec![]));
+        assert_eq!(None, invert_lower_right_block(&m[1..].to_vec()));
+    }
+
+    #[test]
+    fn test_circulant() {
+        let first_row: Vec<Fr> = [2, 1, 1].iter().map(|n| Fr::from(*n)).collect();
+        let m = circulant(&first_row);
+
+        assert_eq!(first_row, m[0]);
+        for i in 1..3 {
+            for j in 0..3 {
+                assert_eq!(m[i - 1][j], m[i][(j + 1) % 3]);
+            }
+        }
+        assert!(is_invertible(&m));
+        assert!(is_mds(&m));
+
+        // All rows equal, so singular.
+        assert!(!is_invertible(&circulant(&[Fr::one(); 3])));
+        assert!(circulant::<Fr>(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_extend_mds() {
+        let base = mds_default::<Fr>(3);
+        let extended = extend_mds(&base, 4).unwrap();
+
+        assert_eq!(4, extended.len());
+        for (base_row, extended_row) in base.iter().zip(&extended) {
+            assert_eq!(base_row[..], extended_row[..3]);
+        }
+        assert!(is_mds(&extended));
+        assert_eq!(extended, transpose(&extended));
+
+        assert_eq!(Some(base.clone()), extend_mds(&base, 3));
+        assert_eq!(None, extend_mds(&base, 2));
+        // The identity has zero entries, so it is not a Cauchy matrix.
+        assert_eq!(None, extend_mds(&matrix::make_identity::<Fr>(3), 4));
+    }
+
     #[test]
     fn test_swapping() {
         test_swapping_aux(3);
@@ -334,6 +727,52 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_compute_pre_sparse_matrix() {
+        for width in 3..9 {
+            let n = 3;
+            let m = generate_mds::<Fr>(width);
+            let pre_sparse = compute_pre_sparse_matrix(&m, n);
+
+            assert_eq!(width, matrix::rows(&pre_sparse));
+            assert!(pre_sparse.iter().all(|row| row.len() == width));
+
+            let (_, sparse) = factor_to_sparse_matrices(m.clone(), n);
+            let product = sparse
+                .iter()
+                .fold(pre_sparse, |acc, s| mat_mul(&acc, s).unwrap());
+            assert_eq!(matrix_power(&m, n + 1), product);
+        }
+    }
+
+    #[test]
+    fn test_verify_sparse_factorization() {
+        for width in 3..9 {
+            let m = generate_mds::<Fr>(width);
+            let (pre_sparse, mut sparse) = factor_to_sparse_matrixes(m.clone(), 3);
+
+            assert!(verify_sparse_factorization(&m, &pre_sparse, &sparse));
+
+            sparse[1].v_rest[0] += Fr::one();
+            assert!(!verify_sparse_factorization(&m, &pre_sparse, &sparse));
+        }
+    }
+
+    #[test]
+    fn test_sparse_matrix_apply() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        for width in 3..9 {
+            let m = generate_mds::<Fr>(width);
+            let (_, sparse) = factor_to_sparse_matrixes(m, 3);
+            let v = (0..width).map(|_| Fr::random(&mut rng)).collect::<Vec<_>>();
+
+            for s in sparse.iter() {
+                assert_eq!(apply_matrix(&s.to_matrix(), &v), s.apply(&v));
+            }
+        }
+    }
+
     #[test]
     fn test_factor_to_sparse_matrixes() {
         for width in 3..9 {
@@ -1,14 +1,18 @@
 use crate::hash_type::HashType;
 use crate::matrix::Matrix;
-use crate::mds::{create_mds_matrices, factor_to_sparse_matrixes, MdsMatrices, SparseMatrix};
+use crate::mds::{
+    create_mds_matrices, derive_mds_matrices, factor_to_sparse_matrixes, is_mds, MdsMatrices,
+    SparseMatrix,
+};
 use crate::poseidon_alt::{hash_correct, hash_optimized_dynamic};
 use crate::preprocessing::compress_round_constants;
-use crate::{matrix, quintic_s_box, BatchHasher, Strength, DEFAULT_STRENGTH};
-use crate::{round_constants, round_numbers, Error};
+use crate::{is_valid_sbox_degree, round_constants, round_numbers_with_sbox_degree, Error};
+use crate::{matrix, s_box, BatchHasher, Strength, DEFAULT_SBOX_DEGREE, DEFAULT_STRENGTH};
+use alloc::vec::Vec;
+use core::marker::PhantomData;
 use ff::PrimeField;
 use generic_array::{sequence::GenericSequence, typenum, ArrayLength, GenericArray};
 use serde::{Deserialize, Serialize};
-use std::marker::PhantomData;
 use typenum::marker_traits::Unsigned;
 use typenum::*;
 
@@ -72,6 +76,8 @@ where
     A: Arity<F>,
 {
     pub mds_matrices: MdsMatrices<F>,
+    /// The determinant of `mds_matrices.m`, computed once at construction.
+    pub(crate) mds_determinant: F,
     pub round_constants: Option<Vec<F>>,
     pub compressed_round_constants: Vec<F>,
     pub pre_sparse_matrix: Matrix<F>,
@@ -84,6 +90,8 @@ where
     pub half_full_rounds: usize,
     pub partial_rounds: usize,
     pub hash_type: HashType<F, A>,
+    /// The degree `d` of the S-box `x^d`, either 3 or 5.
+    pub(crate) sbox_degree: u8,
     pub(crate) _a: PhantomData<A>,
 }
 
@@ -138,16 +146,109 @@ where
         Self::new_with_strength_and_type(strength, HashType::MerkleTree)
     }
 
+    /// Like `new_with_strength_and_type`, but returns an error rather than panicking if the arity or the hash type
+    /// is unsupported.
+    pub fn try_new_with_strength_and_type(
+        strength: Strength,
+        hash_type: HashType<F, A>,
+    ) -> Result<Self, Error> {
+        Self::try_new_with_sbox_degree(strength, hash_type, DEFAULT_SBOX_DEGREE)
+    }
+
+    /// Like `try_new_with_strength_and_type`, but for the S-box `x^sbox_degree`. Returns an error if the degree is
+    /// neither 3 nor 5, or if the S-box is not a permutation of `F`.
+    pub fn try_new_with_sbox_degree(
+        strength: Strength,
+        hash_type: HashType<F, A>,
+        sbox_degree: u8,
+    ) -> Result<Self, Error> {
+        let arity = A::to_usize();
+        if arity == 0 {
+            return Err(Error::UnsupportedArity(arity));
+        }
+        if !hash_type.is_supported() {
+            return Err(Error::UnsupportedHashType);
+        }
+        if !is_valid_sbox_degree::<F>(sbox_degree) {
+            return Err(Error::UnsupportedSBoxDegree(sbox_degree));
+        }
+        Ok(Self::new_with_sbox_degree(strength, hash_type, sbox_degree))
+    }
+
     pub fn new_with_strength_and_type(strength: Strength, hash_type: HashType<F, A>) -> Self {
+        Self::new_with_sbox_degree(strength, hash_type, DEFAULT_SBOX_DEGREE)
+    }
+
+    pub(crate) fn new_with_sbox_degree(
+        strength: Strength,
+        hash_type: HashType<F, A>,
+        sbox_degree: u8,
+    ) -> Self {
         assert!(hash_type.is_supported());
         let arity = A::to_usize();
         let width = arity + 1;
 
         let mds_matrices = create_mds_matrices(width);
 
-        let (full_rounds, partial_rounds) = round_numbers(arity, &strength);
+        let (full_rounds, partial_rounds) =
+            round_numbers_with_sbox_degree(arity, &strength, sbox_degree);
+        let round_constants = round_constants(arity, &strength, sbox_degree);
+
+        Self::from_parts(
+            strength,
+            hash_type,
+            sbox_degree,
+            mds_matrices,
+            round_constants,
+            full_rounds,
+            partial_rounds,
+        )
+    }
+
+    /// Like `new`, but with `mds` in place of the default MDS matrix. Returns `Error::NotMds`, before any
+    /// preprocessing, unless `mds` is an MDS matrix of the permutation's width. As with `is_mds`, the check is
+    /// exponential in the width, so this is only practical for small arities.
+    pub fn from_mds(mds: Matrix<F>) -> Result<Self, Error> {
+        let width = A::ConstantsSize::to_usize();
+        if !matrix::validate_matrix(&mds) || mds.len() != width || !is_mds(&mds) {
+            return Err(Error::NotMds);
+        }
+
+        let arity = A::to_usize();
+        let strength = DEFAULT_STRENGTH;
+        let (full_rounds, partial_rounds) =
+            round_numbers_with_sbox_degree(arity, &strength, DEFAULT_SBOX_DEGREE);
+        let round_constants = round_constants(arity, &strength, DEFAULT_SBOX_DEGREE);
+
+        Ok(Self::from_parts(
+            strength,
+            HashType::MerkleTree,
+            DEFAULT_SBOX_DEGREE,
+            derive_mds_matrices(mds),
+            round_constants,
+            full_rounds,
+            partial_rounds,
+        ))
+    }
+
+    /// Preprocess the given MDS matrices and (uncompressed) round constants into constants for a permutation with
+    /// the given round numbers.
+    pub(crate) fn from_parts(
+        strength: Strength,
+        hash_type: HashType<F, A>,
+        sbox_degree: u8,
+        mds_matrices: MdsMatrices<F>,
+        round_constants: Vec<F>,
+        full_rounds: usize,
+        partial_rounds: usize,
+    ) -> Self {
+        assert!(
+            is_valid_sbox_degree::<F>(sbox_degree),
+            "unsupported S-box degree: {}",
+            sbox_degree
+        );
+        let width = A::ConstantsSize::to_usize();
         let half_full_rounds = full_rounds / 2;
-        let round_constants = round_constants(arity, &strength);
         let compressed_round_constants = compress_round_constants(
             width,
             full_rounds,
@@ -172,6 +273,7 @@ where
         );
 
         Self {
+            mds_determinant: matrix::determinant_via_elimination(&mds_matrices.m),
             mds_matrices,
             round_constants: Some(round_constants),
             compressed_round_constants,
@@ -183,11 +285,12 @@ where
             half_full_rounds,
             partial_rounds,
             hash_type,
+            sbox_degree,
             _a: PhantomData::<A>,
         }
     }
 
-    /// Returns the width.
+    /// Returns the arity.
     #[inline]
     pub fn arity(&self) -> usize {
         A::to_usize()
@@ -198,6 +301,36 @@ where
     pub fn width(&self) -> usize {
         A::ConstantsSize::to_usize()
     }
+
+    /// Returns the number of full rounds.
+    #[inline]
+    pub fn full_rounds(&self) -> usize {
+        self.full_rounds
+    }
+
+    /// Returns the number of partial rounds.
+    #[inline]
+    pub fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    /// Returns the total number of rounds.
+    #[inline]
+    pub fn total_rounds(&self) -> usize {
+        self.full_rounds + self.partial_rounds
+    }
+
+    /// Returns the determinant of the MDS matrix.
+    #[inline]
+    pub fn mds_determinant(&self) -> F {
+        self.mds_determinant
+    }
+
+    /// Returns the degree `d` of the S-box `x^d`.
+    #[inline]
+    pub fn sbox_degree(&self) -> u8 {
+        self.sbox_degree
+    }
 }
 
 impl<F, A> Default for PoseidonConstants<F, A>
@@ -273,6 +406,24 @@ where
         }
     }
 
+    /// Like `new_with_preimage`, but returns an error rather than panicking if the preimage has the wrong length.
+    pub fn try_new_with_preimage(
+        preimage: &[F],
+        constants: &'a PoseidonConstants<F, A>,
+    ) -> Result<Self, Error> {
+        let expected = match constants.hash_type {
+            HashType::ConstantLength(constant_len) => constant_len,
+            _ => A::to_usize(),
+        };
+        if preimage.len() != expected {
+            return Err(Error::InputLengthMismatch {
+                expected,
+                got: preimage.len(),
+            });
+        }
+        Ok(Self::new_with_preimage(preimage, constants))
+    }
+
     /// Replace the elements with the provided optional items.
     ///
     /// # Panics
@@ -326,6 +477,15 @@ where
         self.hash_in_mode(DEFAULT_HASH_MODE)
     }
 
+    /// Like `hash`, but zeroizes the working state once the digest has been extracted, for use with secret
+    /// preimages. The hasher must be reset before it is used again.
+    #[cfg(feature = "zeroize")]
+    pub fn hash_zeroizing(&mut self) -> F {
+        let digest = self.hash();
+        zeroize::Zeroize::zeroize(self);
+        digest
+    }
+
     pub(crate) fn apply_padding(&mut self) {
         if let HashType::ConstantLength(l) = self.constants.hash_type {
             let final_pos = 1 + (l % self.constants.arity());
@@ -400,6 +560,7 @@ where
                 needed
             );
         }
+        let sbox_degree = self.constants.sbox_degree;
         self.elements
             .iter_mut()
             .zip(post_round_keys)
@@ -413,13 +574,13 @@ where
                 } else {
                     Some(post)
                 };
-                quintic_s_box(l, None, post_key);
+                s_box(sbox_degree, l, None, post_key);
             });
         // We need this because post_round_keys will have been empty, so it didn't happen in the for_each. :(
         if last_round {
             self.elements
                 .iter_mut()
-                .for_each(|l| quintic_s_box(l, None, None));
+                .for_each(|l| s_box(sbox_degree, l, None, None));
         } else {
             self.constants_offset += self.elements.len();
         }
@@ -430,8 +591,13 @@ where
     fn partial_round(&mut self) {
         let post_round_key = self.constants.compressed_round_constants[self.constants_offset];
 
-        // Apply the quintic S-Box to the first element
-        quintic_s_box(&mut self.elements[0], None, Some(&post_round_key));
+        // Apply the S-Box to the first element
+        s_box(
+            self.constants.sbox_degree,
+            &mut self.elements[0],
+            None,
+            Some(&post_round_key),
+        );
         self.constants_offset += 1;
 
         self.round_product_mds();
@@ -494,7 +660,7 @@ where
             }
         }
 
-        let _ = std::mem::replace(&mut self.elements, result);
+        let _ = core::mem::replace(&mut self.elements, result);
     }
 
     // Sparse matrix in this context means one of the form, M''.
@@ -518,14 +684,346 @@ where
             val.add_assign(&tmp);
         }
 
-        let _ = std::mem::replace(&mut self.elements, result);
+        let _ = core::mem::replace(&mut self.elements, result);
     }
 
+    #[cfg(feature = "std")]
     pub(crate) fn debug(&self, msg: &str) {
         dbg!(msg, &self.constants_offset, &self.elements);
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<F, A> zeroize::Zeroize for Poseidon<'_, F, A>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    /// Overwrite every state element, including the domain tag, with zero.
+    fn zeroize(&mut self) {
+        crate::zeroize_elements(&mut self.elements);
+        self.reset_offsets();
+    }
+}
+
+/// The matrix which the optimized permutation multiplies the state by in a given round.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundMatrixKind {
+    /// The dense MDS matrix.
+    Mds,
+    /// The pre-sparse matrix, M', in the last of the first half of full rounds.
+    PreSparse,
+    /// The sparse matrix `constants.sparse_matrixes[i]`, in a partial round.
+    Sparse(usize),
+}
+
+/// Which matrix `apply_round_matrix` uses in the given (zero-indexed) round: the pre-sparse matrix in the last of
+/// the first half of full rounds, one of the sparse matrices in each subsequent partial round, and the dense MDS
+/// matrix everywhere else.
+pub fn round_matrix_kind<F, A>(round: usize, constants: &PoseidonConstants<F, A>) -> RoundMatrixKind
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let full_half = constants.half_full_rounds;
+    let sparse_offset = full_half - 1;
+
+    if round == sparse_offset {
+        RoundMatrixKind::PreSparse
+    } else if round > sparse_offset && round < full_half + constants.partial_rounds {
+        RoundMatrixKind::Sparse(round - sparse_offset - 1)
+    } else {
+        RoundMatrixKind::Mds
+    }
+}
+
+/// Right-multiply `state` by the matrix which the optimized permutation uses in the given (zero-indexed) round, as
+/// described by `round_matrix_kind`.
+pub fn apply_round_matrix<F, A>(
+    state: &[F],
+    round: usize,
+    constants: &PoseidonConstants<F, A>,
+) -> Vec<F>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    match round_matrix_kind(round, constants) {
+        RoundMatrixKind::PreSparse => matrix::apply_matrix(&constants.pre_sparse_matrix, state),
+        RoundMatrixKind::Sparse(i) => constants.sparse_matrixes[i].apply(state),
+        RoundMatrixKind::Mds => matrix::apply_matrix(&constants.mds_matrices.m, state),
+    }
+}
+
+/// Apply the optimized permutation used by `hash` to `state`, which must have `constants.width()` elements.
+///
+/// Since round constants are added before every S-box, the all-zero state is not a fixed point: it is mapped to a
+/// fixed, non-zero state determined by the constants alone.
+pub fn permute<F, A>(state: &mut [F], constants: &PoseidonConstants<F, A>)
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mut p = Poseidon::new(constants);
+    p.elements.copy_from_slice(state);
+    p.hash_in_mode(OptimizedStatic);
+    state.copy_from_slice(&p.elements);
+}
+
+/// Apply only the partial rounds of the permutation used by `permute`, with their sparse matrices, to `state`. `state`
+/// must be the state `permute` reaches after its initial full rounds, e.g. to benchmark the partial rounds in isolation.
+pub fn permute_partial_rounds<F, A>(state: &mut [F], constants: &PoseidonConstants<F, A>)
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mut p = Poseidon::new(constants);
+    p.elements.copy_from_slice(state);
+    // Skip the constants added before the first round and those of the initial full rounds.
+    p.current_round = constants.half_full_rounds;
+    p.constants_offset = (constants.half_full_rounds + 1) * constants.width();
+    for _ in 0..constants.partial_rounds {
+        p.partial_round();
+    }
+    state.copy_from_slice(&p.elements);
+}
+
+/// Apply the permutation to `state` without any optimization: every round adds `width` uncompressed round constants
+/// before the S-boxes and multiplies by the dense MDS matrix. This is the reference `permute` is checked against.
+///
+/// # Panics
+///
+/// Panics if `constants` has no `round_constants` (e.g. because it was deserialized) or `state` does not have
+/// `constants.width()` elements.
+pub fn permute_naive<F, A>(state: &mut [F], constants: &PoseidonConstants<F, A>)
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let round_constants = constants
+        .round_constants
+        .as_ref()
+        .expect("naive permutation requires uncompressed round constants");
+    let width = constants.width();
+    assert_eq!(width, state.len(), "Invalid state size");
+
+    for (round, round_keys) in round_constants
+        .chunks(width)
+        .take(constants.total_rounds())
+        .enumerate()
+    {
+        let is_full_round = round < constants.half_full_rounds
+            || round >= constants.half_full_rounds + constants.partial_rounds;
+        for (i, (elt, key)) in state.iter_mut().zip(round_keys).enumerate() {
+            if is_full_round || i == 0 {
+                s_box(constants.sbox_degree, elt, Some(key), None);
+            } else {
+                elt.add_assign(key);
+            }
+        }
+        let mixed = matrix::apply_matrix(&constants.mds_matrices.m, state);
+        state.copy_from_slice(&mixed);
+    }
+}
+
+/// A single unoptimized full round: add `rc` to `state`, apply the S-box of degree `constants.sbox_degree` to every
+/// element and right-multiply by the MDS matrix. Each S-box output is accumulated into the result as soon as it is
+/// computed, so the only allocation is the returned vector.
+pub fn apply_full_round<F, A>(state: &[F], rc: &[F], constants: &PoseidonConstants<F, A>) -> Vec<F>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mds = &constants.mds_matrices.m;
+    assert_eq!(state.len(), rc.len(), "Invalid round constants size");
+    assert_eq!(state.len(), matrix::rows(mds), "Invalid state size");
+
+    let mut result = vec![F::zero(); state.len()];
+    for ((elt, key), row) in state.iter().zip(rc).zip(mds) {
+        let mut x = *elt;
+        s_box(constants.sbox_degree, &mut x, Some(key), None);
+        for (acc, m) in result.iter_mut().zip(row) {
+            *acc += x * m;
+        }
+    }
+    result
+}
+
+/// Hash a single element, e.g. to commit to one value, with a width-2 permutation. The arity 1 domain tag differs
+/// from the arity 2 tag used by `hash2` (as do the constants), so `hash1(a)` does not collide with `hash2(a, 0)`.
+pub fn hash1<F: PrimeField>(a: F, constants: &PoseidonConstants<F, U1>) -> F {
+    Poseidon::new_with_preimage(&[a], constants).hash()
+}
+
+/// Hash exactly two elements, e.g. the children of a node in a binary Merkle tree. The constants' arity is fixed
+/// to 2 by their type, and the state lives on the stack, so this does not allocate.
+pub fn hash2<F: PrimeField>(a: F, b: F, constants: &PoseidonConstants<F, U2>) -> F {
+    Poseidon::new_with_preimage(&[a, b], constants).hash()
+}
+
+/// Hash `inputs` like `hash`, but with the capacity element initialized to `iv` (e.g. a protocol-specific nonce) in
+/// place of the domain tag. As with `Poseidon::new_with_preimage`, `inputs` must have the length `constants` expect.
+pub fn hash_with_iv<F, A>(inputs: &[F], iv: F, constants: &PoseidonConstants<F, A>) -> F
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mut p = Poseidon::new_with_preimage(inputs, constants);
+    p.elements[0] = iv;
+    p.hash()
+}
+
+/// Hash `inputs` preceded by their length, so that a preimage and its zero-extension hash differently even when the
+/// domain tag does not encode the length (e.g. for Merkle tree constants). Returns `Error::FullBuffer` unless
+/// `inputs.len() < arity`.
+pub fn hash_with_length<F, A>(inputs: &[F], constants: &PoseidonConstants<F, A>) -> Result<F, Error>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mut p = Poseidon::new(constants);
+    p.input(F::from(inputs.len() as u64))?;
+    for input in inputs {
+        p.input(*input)?;
+    }
+    Ok(p.hash())
+}
+
+/// Check that `leaf` is at `index` in the Merkle tree with the given `root`, in time independent of the values
+/// involved. `path` holds, from the leaves up, the `arity - 1` siblings of the node at each level, in order and
+/// excluding the node itself. The whole path is always hashed and the computed root compared with `ct_eq_vec`, so a
+/// mismatch is not revealed by when verification stops. Paths of the wrong shape are rejected.
+#[cfg(feature = "ct")]
+pub fn verify_proof_ct<F, A>(
+    leaf: F,
+    index: usize,
+    path: &[Vec<F>],
+    root: F,
+    constants: &PoseidonConstants<F, A>,
+) -> subtle::Choice
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let arity = A::to_usize();
+    if path.iter().any(|siblings| siblings.len() + 1 != arity) {
+        return subtle::Choice::from(0);
+    }
+
+    let (mut node, mut index) = (leaf, index);
+    let mut preimage = Vec::with_capacity(arity);
+    for siblings in path {
+        let position = index % arity;
+        preimage.clear();
+        preimage.extend_from_slice(&siblings[..position]);
+        preimage.push(node);
+        preimage.extend_from_slice(&siblings[position..]);
+
+        node = Poseidon::new_with_preimage(&preimage, constants).hash();
+        index /= arity;
+    }
+
+    // An index beyond the leaves would otherwise alias a leaf.
+    subtle::Choice::from((index == 0) as u8) & crate::ct_eq_vec(&[node], &[root])
+}
+
+/// Hash `inputs` and squeeze `n` field elements from the resulting state, e.g. to derive several keys from one
+/// preimage. The first element is the digest `hash` would return. Each permutation yields `arity` elements (the
+/// rate), after which the state is permuted again.
+pub fn hash_to_n<F, A>(inputs: &[F], n: usize, constants: &PoseidonConstants<F, A>) -> Vec<F>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let mut p = Poseidon::new_with_preimage(inputs, constants);
+    let mut output = Vec::with_capacity(n);
+    while output.len() < n {
+        p.hash();
+        let needed = n - output.len();
+        output.extend(p.elements[1..].iter().take(needed));
+    }
+    output
+}
+
+/// Hash each of `preimages` independently, in parallel with the `parallel` feature. Every preimage must have the
+/// length `hash` expects for `constants`, otherwise this panics.
+pub fn hash_batch<F, A>(preimages: &[Vec<F>], constants: &PoseidonConstants<F, A>) -> Vec<F>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    #[cfg(feature = "parallel")]
+    use rayon::prelude::*;
+
+    #[cfg(feature = "parallel")]
+    let preimages = preimages.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let preimages = preimages.iter();
+
+    preimages
+        .map(|preimage| Poseidon::new_with_preimage(preimage, constants).hash())
+        .collect()
+}
+
+/// Write test vectors for `permute` and `hash`, using the default constants for arity `A`, to `writer` as JSON:
+///
+/// ```json
+/// { "arity": 2, "permute": [{ "input": ["0x0", ...], "output": [...] }, ...], "hash": [{ "input": [...], "output": "0x..." }, ...] }
+/// ```
+///
+/// Field elements are rendered as big-endian hex, as by `format_matrix`. The inputs are fixed, so the vectors only
+/// change if the hash does, and can be checked by other implementations.
+#[cfg(feature = "std")]
+pub fn dump_test_vectors<F, A, W>(mut writer: W) -> std::io::Result<()>
+where
+    F: PrimeField,
+    A: Arity<F>,
+    W: std::io::Write,
+{
+    const VECTOR_COUNT: usize = 4;
+
+    let constants = PoseidonConstants::<F, A>::new();
+    let width = constants.width();
+    let hex_list = |xs: &[F]| {
+        let entries: Vec<String> = xs
+            .iter()
+            .map(|x| format!("\"{}\"", matrix::format_element(x)))
+            .collect();
+        format!("[{}]", entries.join(", "))
+    };
+
+    let mut permutations = Vec::with_capacity(VECTOR_COUNT);
+    let mut hashes = Vec::with_capacity(VECTOR_COUNT);
+    for i in 0..VECTOR_COUNT {
+        let input: Vec<F> = (0..width)
+            .map(|j| F::from((i * width + j) as u64))
+            .collect();
+        let mut output = input.clone();
+        permute(&mut output, &constants);
+        permutations.push(format!(
+            "{{\"input\": {}, \"output\": {}}}",
+            hex_list(&input),
+            hex_list(&output)
+        ));
+
+        let preimage = &input[1..];
+        let hash = Poseidon::new_with_preimage(preimage, &constants).hash();
+        hashes.push(format!(
+            "{{\"input\": {}, \"output\": \"{}\"}}",
+            hex_list(preimage),
+            matrix::format_element(&hash)
+        ));
+    }
+
+    writeln!(
+        writer,
+        "{{\"arity\": {}, \"permute\": [{}], \"hash\": [{}]}}",
+        A::to_usize(),
+        permutations.join(", "),
+        hashes.join(", ")
+    )
+}
+
 #[derive(Debug)]
 pub struct SimplePoseidonBatchHasher<F, A>
 where
@@ -569,14 +1067,17 @@ where
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
     use crate::sponge::vanilla::SpongeTrait;
+    use crate::test_field::TestField;
     use crate::*;
     use blstrs::Scalar as Fr;
     use ff::Field;
     use generic_array::typenum;
+    use rand::SeedableRng;
+    use rand_xorshift::XorShiftRng;
 
     #[test]
     fn reset() {
@@ -593,6 +1094,495 @@ mod tests {
         assert_eq!(default.constants_offset, h.constants_offset);
     }
 
+    #[test]
+    fn try_new_with_preimage() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let preimage = vec![<Fr as Field>::one(); 3];
+
+        let res = Poseidon::<Fr, U2>::try_new_with_preimage(&preimage, &constants);
+        assert!(matches!(
+            res,
+            Err(Error::InputLengthMismatch {
+                expected: 2,
+                got: 3
+            })
+        ));
+
+        let mut h = Poseidon::<Fr, U2>::try_new_with_preimage(&preimage[..2], &constants).unwrap();
+        let mut h2 = Poseidon::<Fr, U2>::new_with_preimage(&preimage[..2], &constants);
+        assert_eq!(h.hash(), h2.hash());
+    }
+
+    #[test]
+    fn round_matrix_kind() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let half = constants.half_full_rounds;
+        let partial = constants.partial_rounds;
+
+        let kinds: Vec<_> = (0..constants.total_rounds())
+            .map(|round| super::round_matrix_kind(round, &constants))
+            .collect();
+
+        let mut expected = vec![RoundMatrixKind::Mds; half - 1];
+        expected.push(RoundMatrixKind::PreSparse);
+        expected.extend((0..partial).map(RoundMatrixKind::Sparse));
+        expected.extend(vec![RoundMatrixKind::Mds; half]);
+        assert_eq!(expected, kinds);
+        assert_eq!(
+            Some(&RoundMatrixKind::Sparse(partial - 1)),
+            kinds.get(half + partial - 1)
+        );
+        assert_eq!(partial, constants.sparse_matrixes.len());
+    }
+
+    #[test]
+    fn hash1() {
+        let constants = PoseidonConstants::<Fr, U1>::new();
+        let a = Fr::from(3);
+
+        let expected = Poseidon::<Fr, U1>::new_with_preimage(&[a], &constants).hash();
+        assert_eq!(expected, super::hash1(a, &constants));
+        assert_ne!(expected, super::hash1(Fr::zero(), &constants));
+        assert_ne!(
+            HashType::<Fr, U1>::MerkleTree.domain_tag(),
+            HashType::<Fr, U2>::MerkleTree.domain_tag()
+        );
+
+        let constants2 = PoseidonConstants::<Fr, U2>::new();
+        assert_ne!(expected, super::hash2(a, Fr::zero(), &constants2));
+        assert_ne!(
+            super::hash1(Fr::zero(), &constants),
+            super::hash2(Fr::zero(), Fr::zero(), &constants2)
+        );
+    }
+
+    #[test]
+    fn hash2() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let a = Fr::from(3);
+        let b = Fr::from(4);
+
+        let expected = Poseidon::<Fr, U2>::new_with_preimage(&[a, b], &constants).hash();
+        assert_eq!(expected, super::hash2(a, b, &constants));
+        assert_ne!(expected, super::hash2(b, a, &constants));
+
+        let constant_length = PoseidonConstants::<Fr, U2>::new_constant_length(2);
+        let expected = Poseidon::<Fr, U2>::new_with_preimage(&[a, b], &constant_length).hash();
+        assert_eq!(expected, super::hash2(a, b, &constant_length));
+    }
+
+    #[test]
+    fn permute_matches_naive() {
+        permute_matches_naive_aux::<U2>();
+        permute_matches_naive_aux::<U4>();
+        permute_matches_naive_aux::<U8>();
+    }
+
+    fn permute_matches_naive_aux<A: Arity<Fr>>() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+        let constants = PoseidonConstants::<Fr, A>::new();
+
+        for _ in 0..5 {
+            let state: Vec<Fr> = (0..constants.width())
+                .map(|_| Fr::random(&mut rng))
+                .collect();
+            let mut optimized = state.clone();
+            let mut naive = state;
+            permute(&mut optimized, &constants);
+            permute_naive(&mut naive, &constants);
+            assert_eq!(optimized, naive);
+        }
+
+        // On the initial state, the permutation's second element is the digest.
+        let preimage = vec![Fr::from(7); A::to_usize()];
+        let mut state = vec![constants.domain_tag];
+        state.extend(&preimage);
+        permute_naive(&mut state, &constants);
+        assert_eq!(
+            Poseidon::new_with_preimage(&preimage, &constants).hash(),
+            state[1]
+        );
+    }
+
+    #[test]
+    fn permute_partial_rounds() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+        let constants = PoseidonConstants::<Fr, U4>::new();
+        let state: Vec<Fr> = (0..constants.width())
+            .map(|_| Fr::random(&mut rng))
+            .collect();
+
+        let mut p = Poseidon::new(&constants);
+        p.elements.copy_from_slice(&state);
+        p.add_round_constants();
+        for _ in 0..constants.half_full_rounds {
+            p.full_round(false);
+        }
+
+        let before = p.elements.to_vec();
+        let mut partial = before.clone();
+        super::permute_partial_rounds(&mut partial, &constants);
+        assert_ne!(before, partial);
+
+        for _ in 0..constants.partial_rounds {
+            p.partial_round();
+        }
+        assert_eq!(p.elements.to_vec(), partial);
+
+        // Sandwiched between the full rounds, this is the whole permutation.
+        for _ in 1..constants.half_full_rounds {
+            p.full_round(false);
+        }
+        p.full_round(true);
+        let mut expected = state;
+        permute(&mut expected, &constants);
+        assert_eq!(expected, p.elements.to_vec());
+    }
+
+    #[test]
+    fn permute_zero_state() {
+        permute_zero_state_aux::<U2>();
+        permute_zero_state_aux::<U4>();
+        permute_zero_state_aux::<U8>();
+    }
+
+    fn permute_zero_state_aux<A: Arity<Fr>>() {
+        let constants = PoseidonConstants::<Fr, A>::new();
+        let zero = vec![Fr::zero(); constants.width()];
+
+        // If round constants were not added, zero would be a fixed point of every round.
+        let mut state = zero.clone();
+        permute(&mut state, &constants);
+        assert_ne!(zero, state);
+        assert!(state.iter().all(|x| *x != Fr::zero()));
+
+        let mut naive = zero;
+        permute_naive(&mut naive, &constants);
+        assert_eq!(naive, state);
+    }
+
+    #[test]
+    fn apply_full_round() {
+        apply_full_round_aux::<Fr>(5);
+        apply_full_round_aux::<TestField>(3);
+    }
+
+    fn apply_full_round_aux<F: PrimeField>(sbox_degree: u8) {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+        let constants = PoseidonConstants::<F, U4>::try_new_with_sbox_degree(
+            Strength::Standard,
+            HashType::MerkleTree,
+            sbox_degree,
+        )
+        .unwrap();
+        let width = constants.width();
+        let state: Vec<F> = (0..width).map(|_| F::random(&mut rng)).collect();
+        let round_constants = constants.round_constants.as_ref().unwrap();
+        let rc = &round_constants[..width];
+
+        let mut separate = crate::matrix::vec_add(&state, rc);
+        separate
+            .iter_mut()
+            .for_each(|x| *x = x.pow_vartime([u64::from(sbox_degree)]));
+        let separate = crate::matrix::apply_matrix(&constants.mds_matrices.m, &separate);
+        assert_eq!(separate, super::apply_full_round(&state, rc, &constants));
+
+        // Without partial rounds, the naive permutation is just a sequence of full rounds.
+        let mut naive = state;
+        let full_rounds_only = PoseidonConstants::<F, U4> {
+            partial_rounds: 0,
+            full_rounds: 2,
+            half_full_rounds: 1,
+            round_constants: Some(round_constants[..2 * width].to_vec()),
+            ..constants.clone()
+        };
+        permute_naive(&mut naive, &full_rounds_only);
+        let twice =
+            super::apply_full_round(&separate, &round_constants[width..2 * width], &constants);
+        assert_eq!(naive, twice);
+    }
+
+    #[test]
+    fn from_mds() {
+        let one = Fr::one();
+        let res = PoseidonConstants::<Fr, U1>::from_mds(vec![vec![one, one], vec![one, one]]);
+        assert!(matches!(res, Err(Error::NotMds)));
+
+        let res = PoseidonConstants::<Fr, U1>::from_mds(crate::mds::mds_default(3));
+        assert!(matches!(res, Err(Error::NotMds)));
+        let res = PoseidonConstants::<Fr, U1>::from_mds(vec![vec![one, one], vec![one]]);
+        assert!(matches!(res, Err(Error::NotMds)));
+
+        let constants = PoseidonConstants::<Fr, U2>::from_mds(crate::mds::mds_default(3)).unwrap();
+        assert_eq!(PoseidonConstants::<Fr, U2>::new(), constants);
+
+        // Any MDS matrix yields a consistent permutation.
+        let mds = vec![
+            vec![Fr::from(2), Fr::from(3)],
+            vec![Fr::from(3), Fr::from(7)],
+        ];
+        let constants = PoseidonConstants::<Fr, U1>::from_mds(mds.clone()).unwrap();
+        assert_eq!(mds, constants.mds_matrices.m);
+        let mut state = vec![Fr::from(1), Fr::from(2)];
+        let mut naive = state.clone();
+        permute(&mut state, &constants);
+        permute_naive(&mut naive, &constants);
+        assert_eq!(naive, state);
+    }
+
+    #[test]
+    fn mds_determinant() {
+        let constants = PoseidonConstants::<Fr, U4>::new();
+        assert_eq!(
+            matrix::determinant(&constants.mds_matrices.m),
+            constants.mds_determinant()
+        );
+        assert_ne!(Fr::zero(), constants.mds_determinant());
+
+        let deserialized: PoseidonConstants<Fr, U4> =
+            serde_json::from_slice(&serde_json::to_vec(&constants).unwrap()).unwrap();
+        assert_eq!(constants.mds_determinant(), deserialized.mds_determinant());
+    }
+
+    #[test]
+    fn hash_with_iv() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let inputs = [Fr::from(1), Fr::from(2)];
+
+        assert_eq!(
+            Poseidon::new_with_preimage(&inputs, &constants).hash(),
+            super::hash_with_iv(&inputs, constants.domain_tag, &constants)
+        );
+        assert_ne!(
+            super::hash_with_iv(&inputs, Fr::from(7), &constants),
+            super::hash_with_iv(&inputs, Fr::from(8), &constants)
+        );
+
+        let constants = PoseidonConstants::<Fr, U4>::new_constant_length(3);
+        let inputs = [Fr::from(1), Fr::from(2), Fr::from(3)];
+        assert_eq!(
+            Poseidon::new_with_preimage(&inputs, &constants).hash(),
+            super::hash_with_iv(&inputs, constants.domain_tag, &constants)
+        );
+    }
+
+    #[test]
+    fn hash_with_length() {
+        let constants = PoseidonConstants::<Fr, U4>::new();
+        let a = Fr::from(42);
+
+        // Without the length, `[a]` is implicitly padded to `[a, 0]`.
+        let mut p = Poseidon::new(&constants);
+        p.input(a).unwrap();
+        let mut q = Poseidon::new(&constants);
+        q.input(a).unwrap();
+        q.input(Fr::zero()).unwrap();
+        assert_eq!(p.hash(), q.hash());
+
+        let h1 = super::hash_with_length(&[a], &constants).unwrap();
+        let h2 = super::hash_with_length(&[a, Fr::zero()], &constants).unwrap();
+        assert_ne!(h1, h2);
+        assert_eq!(
+            Poseidon::new_with_preimage(&[Fr::one(), a, Fr::zero(), Fr::zero()], &constants).hash(),
+            h1
+        );
+
+        assert!(super::hash_with_length(&[a; 3], &constants).is_ok());
+        assert!(matches!(
+            super::hash_with_length(&[a; 4], &constants),
+            Err(Error::FullBuffer)
+        ));
+    }
+
+    #[cfg(feature = "ct")]
+    #[test]
+    fn verify_proof_ct() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let hash = |a: Fr, b: Fr| Poseidon::new_with_preimage(&[a, b], &constants).hash();
+        let leaves: Vec<Fr> = (0..4).map(Fr::from).collect();
+        let (left, right) = (hash(leaves[0], leaves[1]), hash(leaves[2], leaves[3]));
+        let root = hash(left, right);
+
+        let verify = |leaf, index, path: &[Vec<Fr>]| {
+            super::verify_proof_ct(leaf, index, path, root, &constants).unwrap_u8()
+        };
+        let path = [vec![leaves[3]], vec![left]];
+        assert_eq!(1, verify(leaves[2], 2, &path));
+        assert_eq!(1, verify(leaves[1], 1, &[vec![leaves[0]], vec![right]]));
+
+        // Tampering with the leaf, the index or the path is detected.
+        assert_eq!(0, verify(Fr::from(5), 2, &path));
+        assert_eq!(0, verify(leaves[2], 3, &path));
+        assert_eq!(0, verify(leaves[2], 6, &path));
+        assert_eq!(0, verify(leaves[2], 2, &[vec![leaves[3]], vec![right]]));
+        assert_eq!(0, verify(leaves[2], 2, &path[..1]));
+        assert_eq!(
+            0,
+            verify(leaves[2], 2, &[vec![leaves[3]], vec![left, left]])
+        );
+    }
+
+    #[test]
+    fn hash_to_n() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let inputs = [Fr::from(1), Fr::from(2)];
+        let expected = Poseidon::<Fr, U2>::new_with_preimage(&inputs, &constants).hash();
+
+        let output = super::hash_to_n(&inputs, 3, &constants);
+        assert_eq!(3, output.len());
+        assert_eq!(expected, output[0]);
+        assert_ne!(output[0], output[1]);
+        assert_ne!(output[0], output[2]);
+        assert_ne!(output[1], output[2]);
+
+        // Squeezing fewer elements yields a prefix.
+        assert_eq!(output[..1], super::hash_to_n(&inputs, 1, &constants)[..]);
+        assert!(super::hash_to_n(&inputs, 0, &constants).is_empty());
+    }
+
+    #[test]
+    fn hash_batch() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let preimages: Vec<Vec<Fr>> = (0..10_000u64)
+            .map(|i| vec![Fr::from(i), Fr::from(i + 1)])
+            .collect();
+
+        let hashes = super::hash_batch(&preimages, &constants);
+        assert_eq!(preimages.len(), hashes.len());
+        for (preimage, hash) in preimages.iter().zip(&hashes) {
+            assert_eq!(
+                Poseidon::<Fr, U2>::new_with_preimage(preimage, &constants).hash(),
+                *hash
+            );
+        }
+
+        assert!(super::hash_batch(&[], &constants).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid preimage size")]
+    fn hash_batch_wrong_arity() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        super::hash_batch(&[vec![Fr::one(); 3]], &constants);
+    }
+
+    #[test]
+    fn dump_test_vectors() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let mut bytes = Vec::new();
+        super::dump_test_vectors::<Fr, U2, _>(&mut bytes).unwrap();
+
+        let vectors: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        let parse =
+            |x: &serde_json::Value| -> Fr { matrix::parse_element(x.as_str().unwrap()).unwrap() };
+        let parse_list =
+            |xs: &serde_json::Value| xs.as_array().unwrap().iter().map(parse).collect::<Vec<_>>();
+
+        assert_eq!(2, vectors["arity"]);
+        let permutations = vectors["permute"].as_array().unwrap();
+        let hashes = vectors["hash"].as_array().unwrap();
+        assert_eq!(4, permutations.len());
+        assert_eq!(4, hashes.len());
+
+        for vector in permutations {
+            let mut state = parse_list(&vector["input"]);
+            permute(&mut state, &constants);
+            assert_eq!(parse_list(&vector["output"]), state);
+        }
+        for vector in hashes {
+            let preimage = parse_list(&vector["input"]);
+            assert_eq!(
+                parse(&vector["output"]),
+                Poseidon::new_with_preimage(&preimage, &constants).hash()
+            );
+        }
+
+        // The vectors are deterministic.
+        let mut again = Vec::new();
+        super::dump_test_vectors::<Fr, U2, _>(&mut again).unwrap();
+        assert_eq!(bytes, again);
+    }
+
+    #[test]
+    fn try_new_constants() {
+        let res = PoseidonConstants::<Fr, U0>::try_new_with_strength_and_type(
+            Strength::Standard,
+            HashType::ConstantLength(0),
+        );
+        assert!(matches!(res, Err(Error::UnsupportedArity(0))));
+
+        let res = PoseidonConstants::<Fr, U2>::try_new_with_strength_and_type(
+            Strength::Standard,
+            HashType::VariableLength,
+        );
+        assert!(matches!(res, Err(Error::UnsupportedHashType)));
+
+        let constants = PoseidonConstants::<Fr, U2>::try_new_with_strength_and_type(
+            Strength::Standard,
+            HashType::MerkleTree,
+        )
+        .unwrap();
+        assert_eq!(PoseidonConstants::<Fr, U2>::new(), constants);
+    }
+
+    #[test]
+    fn cubic_sbox() {
+        // 3 divides `p - 1` for BLS12-381's scalar field, so `x^3` is not a permutation of it.
+        let res = PoseidonConstants::<Fr, U2>::try_new_with_sbox_degree(
+            Strength::Standard,
+            HashType::MerkleTree,
+            3,
+        );
+        assert!(matches!(res, Err(Error::UnsupportedSBoxDegree(3))));
+        let res = PoseidonConstants::<Fr, U2>::try_new_with_sbox_degree(
+            Strength::Standard,
+            HashType::MerkleTree,
+            7,
+        );
+        assert!(matches!(res, Err(Error::UnsupportedSBoxDegree(7))));
+        let quintic = PoseidonConstants::<Fr, U2>::try_new_with_sbox_degree(
+            Strength::Standard,
+            HashType::MerkleTree,
+            5,
+        )
+        .unwrap();
+        assert_eq!(PoseidonConstants::<Fr, U2>::new(), quintic);
+
+        let constants = PoseidonConstants::<TestField, U2>::try_new_with_sbox_degree(
+            Strength::Standard,
+            HashType::MerkleTree,
+            3,
+        )
+        .unwrap();
+        assert_eq!(3, constants.sbox_degree());
+        assert_eq!(8, constants.full_rounds());
+        assert_eq!(83, constants.partial_rounds());
+        assert_eq!(
+            (constants.full_rounds(), constants.partial_rounds()),
+            round_numbers_with_sbox_degree(2, &Strength::Standard, 3)
+        );
+
+        let preimage = [TestField::from(1), TestField::from(2)];
+
+        // Generated by an independent implementation of the reference Grain LFSR round constants, the Cauchy MDS
+        // matrix `1 / (i + j + t)` and the unoptimized permutation, over `p = 2^255 - 46545`.
+        let expected = TestField::from_str_vartime(
+            "16877253225574496574678950805962077021283134935164451201362168505736030630344",
+        )
+        .unwrap();
+
+        let mut p = Poseidon::<TestField, U2>::new_with_preimage(&preimage, &constants);
+        let mut p2 = p.clone();
+        let mut p3 = p.clone();
+        assert_eq!(expected, p.hash_in_mode(Correct));
+        assert_eq!(expected, p2.hash_in_mode(OptimizedDynamic));
+        assert_eq!(expected, p3.hash_in_mode(OptimizedStatic));
+
+        let quintic = PoseidonConstants::<TestField, U2>::new();
+        let mut quintic_hasher = Poseidon::<TestField, U2>::new_with_preimage(&preimage, &quintic);
+        assert_ne!(expected, quintic_hasher.hash());
+    }
+
     #[test]
     fn hash_det() {
         let test_arity = 2;
@@ -622,6 +1612,34 @@ mod tests {
         assert_eq!(result, h2.hash());
     }
 
+    #[test]
+    fn hash_wide_arity() {
+        // The state is `arity + 1` elements wide, so a full-arity preimage is absorbed in a single permutation.
+        let preimage: Vec<Fr> = (0..11).map(|n| Fr::from(n as u64)).collect();
+        let constants = PoseidonConstants::<Fr, U11>::new();
+
+        let digest = Poseidon::<Fr, U11>::new_with_preimage(&preimage, &constants).hash();
+        let mut h = Poseidon::<Fr, U11>::new(&constants);
+        for x in preimage.iter() {
+            h.input(*x).unwrap();
+        }
+        assert_eq!(digest, h.hash());
+        assert_eq!(
+            digest,
+            Poseidon::<Fr, U11>::new_with_preimage(&preimage, &constants).hash()
+        );
+
+        // A shorter constant-length preimage is padded, and must not collide with the full one.
+        let constants_10 = PoseidonConstants::<Fr, U11>::new_constant_length(10);
+        let digest_10 =
+            Poseidon::<Fr, U11>::new_with_preimage(&preimage[..10], &constants_10).hash();
+        assert_ne!(digest, digest_10);
+
+        let constants_11 = PoseidonConstants::<Fr, U11>::new_constant_length(11);
+        let digest_11 = Poseidon::<Fr, U11>::new_with_preimage(&preimage, &constants_11).hash();
+        assert_ne!(digest_10, digest_11);
+    }
+
     #[test]
     fn hash_values() {
         hash_values_cases(Strength::Standard);
@@ -921,6 +1939,45 @@ mod tests {
         assert_eq!(digest_correct, digest_optimized_static);
     }
 
+    #[test]
+    fn apply_round_matrix_all_rounds() {
+        apply_round_matrix_aux::<U2>();
+        apply_round_matrix_aux::<U4>();
+        apply_round_matrix_aux::<U8>();
+    }
+
+    fn apply_round_matrix_aux<A: Arity<Fr>>() {
+        let constants = PoseidonConstants::<Fr, A>::new();
+        let total_rounds = constants.total_rounds();
+        let state = (0..constants.width())
+            .map(|i| Fr::from(i as u64 + 1))
+            .collect::<Vec<_>>();
+
+        // Without the intervening S-boxes, the pre-sparse and sparse matrices multiply back out to the
+        // dense MDS matrix applied once per round.
+        let optimized = (0..total_rounds).fold(state.clone(), |acc, round| {
+            apply_round_matrix(&acc, round, &constants)
+        });
+        let dense = matrix::apply_matrix_iterated(&constants.mds_matrices.m, &state, total_rounds);
+
+        assert_eq!(dense, optimized);
+    }
+
+    #[test]
+    fn constants_introspection() {
+        let constants = PoseidonConstants::<Fr, U4>::new();
+
+        assert_eq!(4, constants.arity());
+        assert_eq!(5, constants.width());
+        assert_eq!(5, constants.mds_matrices.m.len());
+        assert!(constants.mds_matrices.m.iter().all(|row| row.len() == 5));
+
+        let (full_rounds, partial_rounds) = round_numbers(4, &Strength::Standard);
+        assert_eq!(full_rounds, constants.full_rounds());
+        assert_eq!(partial_rounds, constants.partial_rounds());
+        assert_eq!(full_rounds + partial_rounds, constants.total_rounds());
+    }
+
     #[test]
     fn default_is_standard() {
         let default_constants = PoseidonConstants::<Fr, U8>::new();
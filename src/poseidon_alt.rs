@@ -2,7 +2,8 @@
 //! These are tested (in `poseidon::test`) to be equivalent to the 'static optimized' version
 //! used for actual hashing by the neptune library.
 use crate::poseidon::{Arity, Poseidon};
-use crate::{matrix, quintic_s_box};
+use crate::{matrix, s_box};
+use alloc::vec::Vec;
 use ff::PrimeField;
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -42,7 +43,7 @@ where
     F: PrimeField,
     A: Arity<F>,
 {
-    // Apply the quintic S-Box to all elements, after adding the round key.
+    // Apply the S-Box to all elements, after adding the round key.
     // Round keys are added in the S-box to match circuits (where the addition is free)
     // and in preparation for the shift to adding round keys after (rather than before) applying the S-box.
 
@@ -55,11 +56,12 @@ where
         .skip(p.constants_offset)
         .map(Some);
 
+    let sbox_degree = p.constants.sbox_degree;
     p.elements
         .iter_mut()
         .zip(pre_round_keys)
         .for_each(|(l, pre)| {
-            quintic_s_box(l, pre, None);
+            s_box(sbox_degree, l, pre, None);
         });
 
     p.constants_offset += p.elements.len();
@@ -78,8 +80,8 @@ where
     // Every element of the hash buffer is incremented by the round constants
     add_round_constants(p);
 
-    // Apply the quintic S-Box to the first element
-    quintic_s_box(&mut p.elements[0], None, None);
+    // Apply the S-Box to the first element
+    s_box(p.constants.sbox_degree, &mut p.elements[0], None, None);
 
     // Multiply the elements by the constant MDS matrix
     p.product_mds();
@@ -146,6 +148,7 @@ pub fn full_round_dynamic<F, A>(
             }
         });
 
+    let sbox_degree = p.constants.sbox_degree;
     if absorb_next_round_keys {
         // Using the notation from `test_inverse` in matrix.rs:
         // S
@@ -187,14 +190,14 @@ pub fn full_round_dynamic<F, A>(
             .iter_mut()
             .zip(pre_round_keys.zip(post_round_keys))
             .for_each(|(l, (pre, post))| {
-                quintic_s_box(l, pre, Some(post));
+                s_box(sbox_degree, l, pre, Some(post));
             });
     } else {
         p.elements
             .iter_mut()
             .zip(pre_round_keys)
             .for_each(|(l, pre)| {
-                quintic_s_box(l, pre, None);
+                s_box(sbox_degree, l, pre, None);
             });
     }
     let mut consumed = 0;
@@ -219,8 +222,8 @@ where
     F: PrimeField,
     A: Arity<F>,
 {
-    // Apply the quintic S-Box to the first element
-    quintic_s_box(&mut p.elements[0], None, None);
+    // Apply the S-Box to the first element
+    s_box(p.constants.sbox_degree, &mut p.elements[0], None, None);
 
     // Multiply the elements by the constant MDS matrix
     p.product_mds();
@@ -1,16 +1,36 @@
-use crate::matrix::{apply_matrix, vec_add};
-use crate::mds::MdsMatrices;
+use crate::matrix::{apply_matrix, invert, rows, vec_add, Matrix};
+use crate::mds::{derive_mds_matrices, MdsMatrices};
 use crate::quintic_s_box;
+use alloc::vec::Vec;
 use ff::PrimeField;
 
+/// Fold the round constants `rc` (`t` per round) through the inverse MDS matrix `mds_inv`, so they need only be added
+/// after each S-box: `t` constants per full round, and a single constant per partial round. These are the compressed
+/// constants consumed by the optimized permutation.
+pub fn optimize_round_constants<F: PrimeField>(
+    rc: &[F],
+    mds_inv: &Matrix<F>,
+    t: usize,
+    full: usize,
+    partial: usize,
+) -> Vec<F> {
+    assert_eq!(t, rows(mds_inv), "MDS inverse must be t×t.");
+    assert!(
+        t * (full + partial) <= rc.len(),
+        "Not enough round constants"
+    );
+    let mds = invert(mds_inv).expect("MDS inverse must be invertible");
+
+    compress_round_constants(t, full, partial, rc, &derive_mds_matrices(mds), partial)
+}
+
 // - Compress constants by pushing them back through linear layers and through the identity components of partial layers.
 // - As a result, constants need only be added after each S-box.
-#[allow(clippy::ptr_arg)]
 pub(crate) fn compress_round_constants<F: PrimeField>(
     width: usize,
     full_rounds: usize,
     partial_rounds: usize,
-    round_constants: &Vec<F>,
+    round_constants: &[F],
     mds_matrices: &MdsMatrices<F>,
     partial_preprocessed: usize,
 ) -> Vec<F> {
@@ -171,3 +191,39 @@ pub(crate) fn compress_round_constants<F: PrimeField>(
 
     res
 }
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::poseidon::{HashMode, PoseidonConstants};
+    use crate::Poseidon;
+    use blstrs::Scalar as Fr;
+    use generic_array::typenum::U4;
+
+    #[test]
+    fn test_optimize_round_constants() {
+        let constants = PoseidonConstants::<Fr, U4>::new();
+        let optimized = optimize_round_constants(
+            constants.round_constants.as_ref().unwrap(),
+            &constants.mds_matrices.m_inv,
+            constants.width(),
+            constants.full_rounds,
+            constants.partial_rounds,
+        );
+        assert_eq!(
+            constants.full_rounds * constants.width() + constants.partial_rounds,
+            optimized.len()
+        );
+
+        let optimized_constants = PoseidonConstants {
+            compressed_round_constants: optimized,
+            ..constants.clone()
+        };
+        let preimage: Vec<Fr> = (1..=4).map(Fr::from).collect();
+        let naive =
+            Poseidon::new_with_preimage(&preimage, &constants).hash_in_mode(HashMode::Correct);
+        let optimized = Poseidon::new_with_preimage(&preimage, &optimized_constants)
+            .hash_in_mode(HashMode::OptimizedStatic);
+        assert_eq!(naive, optimized);
+    }
+}
@@ -123,6 +123,10 @@ where
         max_batch_size: usize,
     ) -> Result<Self, Error> {
         let constants = GpuConstants(PoseidonConstants::<F, A>::new_with_strength(strength));
+        // The kernels in `sources` are generated for the quintic S-box only.
+        if constants.0.sbox_degree() != 5 {
+            return Err(Error::UnsupportedSBoxDegree(constants.0.sbox_degree()));
+        }
         let program = ec_gpu_gen::program!(device)?;
 
         // Allocate the buffer only once and re-use it in the hashing steps
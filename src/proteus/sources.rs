@@ -145,8 +145,9 @@ where
 ///
 /// It returns both, the standard and the strengthened constants.
 fn derive_constants(arity: usize) -> (DerivedConstants, DerivedConstants) {
-    let (full_standard, partial_standard) = round_numbers_base(arity);
-    let (full_strengthened, partial_strengthened) = round_numbers_strengthened(arity);
+    // The kernels only implement the quintic S-box.
+    let (full_standard, partial_standard) = round_numbers_base(arity, 5);
+    let (full_strengthened, partial_strengthened) = round_numbers_strengthened(arity, 5);
     (
         DerivedConstants::new(arity, full_standard, partial_standard),
         DerivedConstants::new(arity, full_strengthened, partial_strengthened),
@@ -0,0 +1,89 @@
+use core::ops::{AddAssign, MulAssign};
+use ff::PrimeField;
+
+/// The element operations which matrix multiplication needs, so that `matrix::mat_mul` and `matrix::apply_matrix`
+/// can be carried out over any commutative ring (e.g. polynomials, or integers modulo a composite), not just a
+/// `PrimeField`. `one` and `PartialEq` let `apply_matrix` recognize the identity.
+pub trait Ring: Clone + PartialEq {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn add_assign(&mut self, other: &Self);
+    fn mul_assign(&mut self, other: &Self);
+}
+
+impl<F: PrimeField> Ring for F {
+    fn zero() -> Self {
+        F::zero()
+    }
+
+    fn one() -> Self {
+        F::one()
+    }
+
+    fn add_assign(&mut self, other: &Self) {
+        AddAssign::add_assign(self, other);
+    }
+
+    fn mul_assign(&mut self, other: &Self) {
+        MulAssign::mul_assign(self, other);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matrix::{apply_matrix, mat_mul, Matrix};
+
+    /// Integers modulo 12, which has zero divisors and so is a ring but not a field.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Z12(u8);
+
+    impl Ring for Z12 {
+        fn zero() -> Self {
+            Z12(0)
+        }
+
+        fn one() -> Self {
+            Z12(1)
+        }
+
+        fn add_assign(&mut self, other: &Self) {
+            self.0 = (self.0 + other.0) % 12;
+        }
+
+        fn mul_assign(&mut self, other: &Self) {
+            self.0 = (self.0 * other.0) % 12;
+        }
+    }
+
+    fn z12(rows: &[&[u8]]) -> Matrix<Z12> {
+        rows.iter()
+            .map(|row| row.iter().map(|x| Z12(*x)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_mat_mul_z12() {
+        let a = z12(&[&[2, 3], &[4, 6]]);
+        let b = z12(&[&[6, 4], &[4, 6]]);
+        // [[12 + 12, 8 + 18], [24 + 24, 16 + 36]] mod 12
+        assert_eq!(Some(z12(&[&[0, 2], &[0, 4]])), mat_mul(&a, &b));
+
+        let identity = z12(&[&[1, 0], &[0, 1]]);
+        assert_eq!(Some(a.clone()), mat_mul(&a, &identity));
+        assert_eq!(Some(a.clone()), mat_mul(&identity, &a));
+
+        assert_eq!(None, mat_mul(&a, &z12(&[&[1, 2]])));
+
+        // Long enough rows to take the batched path with the `fast-field` feature.
+        let row = z12(&[&[1; 9]]);
+        let column = z12(&[&[5u8][..]; 9]);
+        assert_eq!(Some(z12(&[&[9]])), mat_mul(&row, &column));
+
+        assert_eq!(vec![Z12(10), Z12(3)], apply_matrix(&a, &[Z12(1), Z12(2)]));
+        assert_eq!(
+            vec![Z12(7), Z12(5)],
+            apply_matrix(&identity, &[Z12(7), Z12(5)])
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use ff::PrimeField;
 
 /// From the paper ():
@@ -36,7 +37,7 @@ pub fn generate_constants<F: PrimeField>(
     if n_bytes != 32 {
         unimplemented!("neptune currently supports 32-byte fields exclusively");
     }
-    assert_eq!((field_size as f32 / 8.0).ceil() as usize, n_bytes);
+    assert_eq!((field_size as usize + 7) / 8, n_bytes);
 
     let num_constants = (r_f + r_p) * t;
     let mut init_sequence: Vec<bool> = Vec::new();
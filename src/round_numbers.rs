@@ -19,10 +19,10 @@ const fn n_sboxes(t: usize, rf: usize, rp: usize) -> usize {
     t * rf + rp
 }
 
-// Returns the round numbers for a given arity `(R_F, R_P)`.
-pub(crate) fn round_numbers_base(arity: usize) -> (usize, usize) {
+// Returns the round numbers for a given arity and S-box degree `(R_F, R_P)`.
+pub(crate) fn round_numbers_base(arity: usize, sbox_degree: u8) -> (usize, usize) {
     let t = arity + 1;
-    calc_round_numbers(t, true)
+    calc_round_numbers(t, sbox_degree, true)
 }
 
 // In case of newly-discovered attacks, we may need stronger security.
@@ -33,29 +33,33 @@ pub(crate) fn round_numbers_base(arity: usize) -> (usize, usize) {
 // Then it is unlikely that a new attack breaks through this number,
 // but even if this happens then the complexity is almost surely above 2^64, and you will be safe."
 // - D Khovratovich
-pub(crate) fn round_numbers_strengthened(arity: usize) -> (usize, usize) {
-    let (full_round, partial_rounds) = round_numbers_base(arity);
+pub(crate) fn round_numbers_strengthened(arity: usize, sbox_degree: u8) -> (usize, usize) {
+    let (full_round, partial_rounds) = round_numbers_base(arity, sbox_degree);
 
     // Increase by 25%, rounding up.
-    let strengthened_partial_rounds = f64::ceil(partial_rounds as f64 * 1.25) as usize;
+    let strengthened_partial_rounds = (partial_rounds * 5 + 3) / 4;
 
     (full_round, strengthened_partial_rounds)
 }
 
-// Returns the round numbers for a given width `t`. Here, the `security_margin` parameter does not
+// Returns the round numbers for a given width `t` and S-box degree `alpha`. Here, the `security_margin` parameter does not
 // indicate that we are calculating `R_F` and `R_P` for the "strengthened" round numbers, done in
 // the function `round_numbers_strengthened()`.
-pub(crate) fn calc_round_numbers(t: usize, security_margin: bool) -> (usize, usize) {
+pub(crate) fn calc_round_numbers(
+    t: usize,
+    sbox_degree: u8,
+    security_margin: bool,
+) -> (usize, usize) {
     let mut rf = 0;
     let mut rp = 0;
     let mut n_sboxes_min = usize::MAX;
 
     for mut rf_test in (2..=1000).step_by(2) {
         for mut rp_test in 4..200 {
-            if round_numbers_are_secure(t, rf_test, rp_test) {
+            if round_numbers_are_secure(t, sbox_degree, rf_test, rp_test) {
                 if security_margin {
                     rf_test += 2;
-                    rp_test = (1.075 * rp_test as f32).ceil() as usize;
+                    rp_test = ceil(1.075 * rp_test as f32) as usize;
                 }
                 let n_sboxes = n_sboxes(t, rf_test, rp_test);
                 if n_sboxes < n_sboxes_min || (n_sboxes == n_sboxes_min && rf_test < rf) {
@@ -70,21 +74,49 @@ pub(crate) fn calc_round_numbers(t: usize, security_margin: bool) -> (usize, usi
     (rf, rp)
 }
 
+// Float rounding and logarithms are not available in `core`, so fall back to `libm` without `std`.
+#[cfg(feature = "std")]
+fn ceil(x: f32) -> f32 {
+    x.ceil()
+}
+
+#[cfg(not(feature = "std"))]
+fn ceil(x: f32) -> f32 {
+    libm::ceilf(x)
+}
+
+#[cfg(feature = "std")]
+fn log2(x: f32) -> f32 {
+    x.log2()
+}
+
+#[cfg(not(feature = "std"))]
+fn log2(x: f32) -> f32 {
+    libm::log2f(x)
+}
+
 // Returns `true` if the provided round numbers satisfy the security inequalities specified in the
 // Poseidon paper.
-fn round_numbers_are_secure(t: usize, rf: usize, rp: usize) -> bool {
+fn round_numbers_are_secure(t: usize, sbox_degree: u8, rf: usize, rp: usize) -> bool {
+    // Approximations of `log_alpha(2)`, `log_alpha(2) / 2` and `log_alpha(2) / 3`, which scale the
+    // interpolation and Groebner basis bounds.
+    let (interp, grob_1, grob_2) = match sbox_degree {
+        3 => (0.63, 0.32, 0.21),
+        5 => (0.43, 0.21, 0.14),
+        _ => panic!("unsupported S-box degree: {}", sbox_degree),
+    };
     let (rp, t, n, m) = (rp as f32, t as f32, PRIME_BITLEN as f32, M as f32);
     let rf_stat = if m <= (n - 3.0) * (t + 1.0) {
         6.0
     } else {
         10.0
     };
-    let rf_interp = 0.43 * m + t.log2() - rp;
-    let rf_grob_1 = 0.21 * n - rp;
-    let rf_grob_2 = (0.14 * n - 1.0 - rp) / (t - 1.0);
+    let rf_interp = interp * m + log2(t) - rp;
+    let rf_grob_1 = grob_1 * n - rp;
+    let rf_grob_2 = (grob_2 * n - 1.0 - rp) / (t - 1.0);
     let rf_max = [rf_stat, rf_interp, rf_grob_1, rf_grob_2]
         .iter()
-        .map(|rf| rf.ceil() as usize)
+        .map(|rf| ceil(*rf) as usize)
         .max()
         .unwrap();
     rf >= rf_max
@@ -121,7 +153,7 @@ mod tests {
             (65, 61),
         ];
         for (t, rp_expected) in cases.iter() {
-            let (rf, rp) = calc_round_numbers(*t, true);
+            let (rf, rp) = calc_round_numbers(*t, 5, true);
             assert_eq!(rf, 8);
             assert_eq!(rp, *rp_expected);
         }
@@ -169,7 +201,7 @@ mod tests {
         );
 
         for line in lines {
-            let (rf, rp) = calc_round_numbers(line.t, true);
+            let (rf, rp) = calc_round_numbers(line.t, 5, true);
             let sbox_cost = n_sboxes(line.t, rf, rp);
             let size_cost = sbox_cost * PRIME_BITLEN;
 
@@ -1,15 +1,33 @@
+use core::fmt;
+use core::marker::PhantomData;
 use ff::PrimeField;
 use serde::{
     de::{self, Deserializer, MapAccess, SeqAccess, Visitor},
     ser::{SerializeStruct, Serializer},
     Deserialize, Serialize,
 };
-use std::fmt;
-use std::marker::PhantomData;
 
 use crate::hash_type::HashType;
+use crate::matrix::{determinant_via_elimination, is_square};
+#[cfg(feature = "serde_json")]
+use crate::matrix::{format_element, is_invertible, minor, parse_element, Matrix};
+#[cfg(feature = "serde_json")]
+use crate::mds::derive_mds_matrices;
+use crate::mds::MdsMatrices;
 use crate::poseidon::PoseidonConstants;
-use crate::Arity;
+#[cfg(any(feature = "cache", feature = "serde_json"))]
+use crate::Error;
+#[cfg(feature = "serde_json")]
+use crate::Strength;
+#[cfg(feature = "cache")]
+use crate::DEFAULT_STRENGTH;
+use crate::{is_valid_sbox_degree, Arity, DEFAULT_SBOX_DEGREE};
+#[cfg(feature = "serde_json")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "cache")]
+use serde::de::DeserializeOwned;
+#[cfg(feature = "cache")]
+use std::path::{Path, PathBuf};
 
 impl<F, A> Serialize for PoseidonConstants<F, A>
 where
@@ -20,7 +38,7 @@ where
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("PoseidonConstants", 8)?;
+        let mut state = serializer.serialize_struct("PoseidonConstants", 9)?;
         state.serialize_field("mds", &self.mds_matrices)?;
         state.serialize_field("crc", &self.compressed_round_constants)?;
         state.serialize_field("psm", &self.pre_sparse_matrix)?;
@@ -29,6 +47,7 @@ where
         state.serialize_field("rf", &self.full_rounds)?;
         state.serialize_field("rp", &self.partial_rounds)?;
         state.serialize_field("ht", &self.hash_type)?;
+        state.serialize_field("sd", &self.sbox_degree)?;
         state.end()
     }
 }
@@ -53,6 +72,7 @@ where
             Rf,
             Rp,
             Ht,
+            Sd,
         }
 
         struct PoseidonConstantsVisitor<F, A>
@@ -79,7 +99,7 @@ where
             where
                 V: SeqAccess<'de>,
             {
-                let mds_matrices = seq
+                let mds_matrices: MdsMatrices<F> = seq
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(0, &self))?;
                 let compressed_round_constants = seq
@@ -103,9 +123,12 @@ where
                 let hash_type: HashType<F, A> = seq
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(7, &self))?;
+                let sbox_degree = checked_sbox_degree::<F, _>(seq.next_element()?)?;
+                let mds_determinant = mds_determinant(&mds_matrices)?;
 
                 Ok(PoseidonConstants {
                     mds_matrices,
+                    mds_determinant,
                     round_constants: None,
                     compressed_round_constants,
                     pre_sparse_matrix,
@@ -116,6 +139,7 @@ where
                     half_full_rounds: full_rounds / 2,
                     partial_rounds,
                     hash_type,
+                    sbox_degree,
                     _a: PhantomData::<A>,
                 })
             }
@@ -132,6 +156,7 @@ where
                 let mut full_rounds = None;
                 let mut partial_rounds = None;
                 let mut hash_type = None;
+                let mut sbox_degree = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
@@ -185,10 +210,16 @@ where
                             }
                             hash_type = Some(map.next_value()?);
                         }
+                        Field::Sd => {
+                            if sbox_degree.is_some() {
+                                return Err(de::Error::duplicate_field("sbox_degree"));
+                            }
+                            sbox_degree = Some(map.next_value()?);
+                        }
                     }
                 }
 
-                let mds_matrices =
+                let mds_matrices: MdsMatrices<F> =
                     mds_matrices.ok_or_else(|| de::Error::missing_field("mds_matrices"))?;
                 let compressed_round_constants = compressed_round_constants
                     .ok_or_else(|| de::Error::missing_field("compressed_round_constants"))?;
@@ -203,8 +234,12 @@ where
                     partial_rounds.ok_or_else(|| de::Error::missing_field("partial_rounds"))?;
                 let hash_type: HashType<F, A> =
                     hash_type.ok_or_else(|| de::Error::missing_field("hash_type"))?;
+                // Constants serialized before the S-box degree was configurable use the quintic S-box.
+                let sbox_degree = checked_sbox_degree::<F, _>(sbox_degree)?;
+                let mds_determinant = mds_determinant(&mds_matrices)?;
                 Ok(PoseidonConstants {
                     mds_matrices,
+                    mds_determinant,
                     round_constants: None,
                     compressed_round_constants,
                     pre_sparse_matrix,
@@ -215,6 +250,7 @@ where
                     half_full_rounds: full_rounds / 2,
                     partial_rounds,
                     hash_type,
+                    sbox_degree,
                     _a: PhantomData::<A>,
                 })
             }
@@ -229,6 +265,7 @@ where
 	  full_rounds,
 	  partial_rounds,
 	  hash_type,
+	  sbox_degree,
 "];
         deserializer.deserialize_struct(
             "PoseidonConstants",
@@ -241,6 +278,215 @@ where
     }
 }
 
+// The determinant cached by `PoseidonConstants`, which is recomputed rather than serialized.
+// Constants serialized before the S-box degree was configurable use the quintic S-box.
+fn checked_sbox_degree<F: PrimeField, E: de::Error>(sbox_degree: Option<u8>) -> Result<u8, E> {
+    let sbox_degree = sbox_degree.unwrap_or(DEFAULT_SBOX_DEGREE);
+    if !is_valid_sbox_degree::<F>(sbox_degree) {
+        return Err(E::custom(format_args!(
+            "unsupported S-box degree: {}",
+            sbox_degree
+        )));
+    }
+    Ok(sbox_degree)
+}
+
+fn mds_determinant<F: PrimeField, E: de::Error>(mds_matrices: &MdsMatrices<F>) -> Result<F, E> {
+    if !is_square(&mds_matrices.m) {
+        return Err(E::custom("MDS matrix is not square"));
+    }
+    Ok(determinant_via_elimination(&mds_matrices.m))
+}
+
+#[cfg(feature = "cache")]
+impl<F, A> PoseidonConstants<F, A>
+where
+    F: PrimeField + Serialize + DeserializeOwned,
+    A: Arity<F>,
+{
+    /// Load the default constants (as returned by `PoseidonConstants::new`) from `path` if it contains a valid
+    /// cached copy for this field and arity. Otherwise generate them and write them to `path`, so subsequent calls
+    /// can skip generation. The cache is written to a temporary file and renamed into place, so a concurrent or
+    /// interrupted write never leaves a truncated cache behind.
+    ///
+    /// Like any deserialized constants, those loaded from the cache have no `round_constants`.
+    pub fn load_or_generate<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let field = format_element(&-F::one());
+        let arity = A::to_usize();
+
+        if let Ok(bytes) = std::fs::read(path) {
+            if let Ok(cached) = serde_json::from_slice::<CacheEntry<Self>>(&bytes) {
+                let constants = cached.constants;
+                let valid = cached.field == field
+                    && cached.arity == arity
+                    && constants.strength == DEFAULT_STRENGTH
+                    && constants.hash_type == HashType::MerkleTree
+                    && constants.sbox_degree == DEFAULT_SBOX_DEGREE
+                    && constants.mds_matrices.m.len() == constants.width();
+                if valid {
+                    return Ok(constants);
+                }
+            }
+        }
+
+        let entry = CacheEntry {
+            field,
+            arity,
+            constants: Self::new(),
+        };
+        let bytes = serde_json::to_vec(&entry)
+            .map_err(|e| Error::Other(format!("failed to serialize constants: {e}")))?;
+
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(format!(".{}.tmp", std::process::id()));
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, bytes)
+            .and_then(|_| std::fs::rename(&tmp, path))
+            .map_err(|e| {
+                let _ = std::fs::remove_file(&tmp);
+                Error::Other(format!(
+                    "failed to write constants cache {}: {e}",
+                    path.display()
+                ))
+            })?;
+
+        Ok(entry.constants)
+    }
+}
+
+/// The on-disk format of `load_or_generate`'s cache: the constants, tagged with the field (by `p - 1` in hex) and
+/// arity they were generated for.
+#[cfg(feature = "cache")]
+#[derive(Serialize, Deserialize)]
+struct CacheEntry<C> {
+    field: String,
+    arity: usize,
+    constants: C,
+}
+
+/// Parameters in the JSON format read by `constants_from_json`, with field elements as big-endian hex strings.
+#[cfg(feature = "serde_json")]
+#[derive(Serialize, Deserialize)]
+struct JsonParameters {
+    mds: Vec<Vec<String>>,
+    round_constants: Vec<String>,
+    full_rounds: usize,
+    partial_rounds: usize,
+    #[serde(default = "default_sbox_degree")]
+    sbox_degree: u8,
+}
+
+#[cfg(feature = "serde_json")]
+fn default_sbox_degree() -> u8 {
+    DEFAULT_SBOX_DEGREE
+}
+
+/// Build Merkle tree constants from externally generated parameters, such as those produced by the reference Poseidon
+/// scripts. `json` is an object of the form
+///
+/// ```json
+/// { "mds": [["0x2", "0x1"], ["0x1", "0x3"]], "round_constants": ["0x1", ...], "full_rounds": 8, "partial_rounds": 55 }
+/// ```
+///
+/// with an optional `"sbox_degree"` (defaulting to 5). `full_rounds` must be even and at least 2, there must be
+/// `width · (full_rounds + partial_rounds)` round constants, and the MDS matrix and its lower-right minor must be
+/// invertible.
+#[cfg(feature = "serde_json")]
+pub fn constants_from_json<F, A>(json: &str) -> Result<PoseidonConstants<F, A>, Error>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let params: JsonParameters = serde_json::from_str(json)
+        .map_err(|e| Error::Other(format!("invalid constants JSON: {e}")))?;
+    let width = A::to_usize() + 1;
+
+    let m = params
+        .mds
+        .iter()
+        .map(|row| row.iter().map(|x| field_from_hex(x)).collect())
+        .collect::<Result<Matrix<F>, Error>>()?;
+    if m.len() != width || m.iter().any(|row| row.len() != width) {
+        return Err(Error::Other(format!("MDS matrix must be {width}×{width}")));
+    }
+    if !is_invertible(&m) || !is_invertible(&minor(&m, 0, 0)) {
+        return Err(Error::Other("MDS matrix is not invertible".into()));
+    }
+
+    // The optimized permutation needs at least one full round on each side of the partial rounds.
+    if params.full_rounds < 2 || params.full_rounds % 2 != 0 {
+        return Err(Error::Other(
+            "the number of full rounds must be even and at least 2".into(),
+        ));
+    }
+    let expected = params
+        .full_rounds
+        .checked_add(params.partial_rounds)
+        .and_then(|rounds| rounds.checked_mul(width))
+        .ok_or_else(|| Error::Other("the number of rounds is too large".into()))?;
+
+    let round_constants = params
+        .round_constants
+        .iter()
+        .map(|x| field_from_hex(x))
+        .collect::<Result<Vec<F>, Error>>()?;
+    if round_constants.len() != expected {
+        return Err(Error::InputLengthMismatch {
+            expected,
+            got: round_constants.len(),
+        });
+    }
+    if !is_valid_sbox_degree::<F>(params.sbox_degree) {
+        return Err(Error::UnsupportedSBoxDegree(params.sbox_degree));
+    }
+
+    Ok(PoseidonConstants::from_parts(
+        Strength::Standard,
+        HashType::MerkleTree,
+        params.sbox_degree,
+        derive_mds_matrices(m),
+        round_constants,
+        params.full_rounds,
+        params.partial_rounds,
+    ))
+}
+
+/// Render `constants` in the format read by `constants_from_json`. Fails if `constants` has no (uncompressed)
+/// `round_constants`, as is the case for deserialized constants.
+#[cfg(feature = "serde_json")]
+pub fn constants_to_json<F, A>(constants: &PoseidonConstants<F, A>) -> Result<String, Error>
+where
+    F: PrimeField,
+    A: Arity<F>,
+{
+    let round_constants = constants
+        .round_constants
+        .as_ref()
+        .ok_or_else(|| Error::Other("constants have no round constants".into()))?;
+    let params = JsonParameters {
+        mds: constants
+            .mds_matrices
+            .m
+            .iter()
+            .map(|row| row.iter().map(format_element).collect())
+            .collect(),
+        round_constants: round_constants.iter().map(format_element).collect(),
+        full_rounds: constants.full_rounds,
+        partial_rounds: constants.partial_rounds,
+        sbox_degree: constants.sbox_degree,
+    };
+
+    serde_json::to_string(&params)
+        .map_err(|e| Error::Other(format!("failed to serialize constants: {e}")))
+}
+
+// Parse a field element from big-endian hex, with or without a `0x` prefix.
+#[cfg(feature = "serde_json")]
+fn field_from_hex<F: PrimeField>(hex: &str) -> Result<F, Error> {
+    parse_element(hex).ok_or_else(|| Error::Other(format!("invalid field element: {hex}")))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -273,6 +519,151 @@ mod tests {
         assert_eq!(h1.hash(), h2.hash())
     }
 
+    #[test]
+    fn serde_rejects_invalid_sbox_degree() {
+        let constants = PoseidonConstants::<Fr, U2>::new();
+        let mut value = serde_json::to_value(&constants).unwrap();
+        assert!(serde_json::from_value::<PoseidonConstants<Fr, U2>>(value.clone()).is_ok());
+
+        // `x^3` is not a permutation of BLS12-381's scalar field.
+        value["sd"] = serde_json::Value::from(3);
+        assert!(serde_json::from_value::<PoseidonConstants<Fr, U2>>(value).is_err());
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn load_or_generate() {
+        let path = std::env::temp_dir().join(format!(
+            "neptune-constants-cache-{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        let generated = PoseidonConstants::<Fr, U2>::load_or_generate(&path).unwrap();
+        assert!(path.exists());
+
+        let cached = PoseidonConstants::<Fr, U2>::load_or_generate(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut expected = generated;
+        expected.round_constants = None;
+        assert_eq!(expected, cached);
+
+        // An invalid cache is regenerated.
+        std::fs::write(&path, b"not constants").unwrap();
+        let regenerated = PoseidonConstants::<Fr, U2>::load_or_generate(&path).unwrap();
+        assert_eq!(PoseidonConstants::<Fr, U2>::new(), regenerated);
+
+        // So is a cache for another arity or field.
+        let regenerated = PoseidonConstants::<Fr, U1>::load_or_generate(&path).unwrap();
+        assert_eq!(PoseidonConstants::<Fr, U1>::new(), regenerated);
+        let regenerated = PoseidonConstants::<S1, U1>::load_or_generate(&path).unwrap();
+        assert_eq!(PoseidonConstants::<S1, U1>::new(), regenerated);
+        let cached = PoseidonConstants::<S1, U1>::load_or_generate(&path).unwrap();
+        assert_eq!(None, cached.round_constants);
+
+        // The temporary file has been renamed into place.
+        let dir = path.parent().unwrap();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(!std::fs::read_dir(dir).unwrap().any(|entry| {
+            let other = entry.unwrap().file_name();
+            let other = other.to_str().unwrap_or_default();
+            other.starts_with(name) && other.ends_with(".tmp")
+        }));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn constants_json_roundtrip() {
+        let json = r#"{
+            "mds": [["0x2", "0x1"], ["0x1", "0x3"]],
+            "round_constants": ["0x1", "0x2", "0x3", "0x4", "0x5", "0x6"],
+            "full_rounds": 2,
+            "partial_rounds": 1
+        }"#;
+        let constants = constants_from_json::<Fr, U1>(json).unwrap();
+
+        let m: Vec<Vec<Fr>> = vec![vec![Fr::from(2), Fr::one()], vec![Fr::one(), Fr::from(3)]];
+        assert_eq!(m, constants.mds_matrices.m);
+        assert_eq!(
+            Some((1..=6).map(Fr::from).collect()),
+            constants.round_constants
+        );
+        assert_eq!(DEFAULT_SBOX_DEGREE, constants.sbox_degree);
+
+        // The preprocessed constants describe the same permutation.
+        let mut state = [Fr::from(7), Fr::from(11)];
+        let mut expected = state;
+        crate::poseidon::permute(&mut state, &constants);
+        crate::poseidon::permute_naive(&mut expected, &constants);
+        assert_eq!(expected, state);
+
+        let rendered = constants_to_json(&constants).unwrap();
+        assert_eq!(constants, constants_from_json(&rendered).unwrap());
+
+        let generated = PoseidonConstants::<Fr, U2>::new();
+        let rendered = constants_to_json(&generated).unwrap();
+        assert_eq!(generated, constants_from_json(&rendered).unwrap());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn constants_from_invalid_json() {
+        let params = |mds: &str, rc: &str| {
+            format!(
+                r#"{{"mds": {mds}, "round_constants": {rc}, "full_rounds": 2, "partial_rounds": 1}}"#
+            )
+        };
+        let mds = r#"[["0x2", "0x1"], ["0x1", "0x3"]]"#;
+        let rc = r#"["0x1", "0x2", "0x3", "0x4", "0x5", "0x6"]"#;
+
+        assert!(constants_from_json::<Fr, U1>(&params(mds, rc)).is_ok());
+        assert!(constants_from_json::<Fr, U1>("{}").is_err());
+        assert!(constants_from_json::<Fr, U2>(&params(mds, rc)).is_err());
+        assert!(matches!(
+            constants_from_json::<Fr, U1>(&params(mds, r#"["0x1"]"#)),
+            Err(Error::InputLengthMismatch {
+                expected: 6,
+                got: 1
+            })
+        ));
+        assert!(
+            constants_from_json::<Fr, U1>(&params(r#"[["0x1", "0x1"], ["0x1", "0x1"]]"#, rc))
+                .is_err()
+        );
+        assert!(
+            constants_from_json::<Fr, U1>(&params(r#"[["0xg", "0x1"], ["0x1", "0x3"]]"#, rc))
+                .is_err()
+        );
+        // The BLS12-381 scalar field modulus is not a canonical field element.
+        let modulus = r#"[["0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001", "0x1"], ["0x1", "0x3"]]"#;
+        assert!(constants_from_json::<Fr, U1>(&params(modulus, rc)).is_err());
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn constants_from_json_invalid_rounds() {
+        let params = |full_rounds: usize, partial_rounds: usize| {
+            format!(
+                r#"{{"mds": [["0x2", "0x1"], ["0x1", "0x3"]], "round_constants": [], "full_rounds": {full_rounds}, "partial_rounds": {partial_rounds}}}"#
+            )
+        };
+        let is_other =
+            |res: Result<PoseidonConstants<Fr, U1>, Error>| matches!(res, Err(Error::Other(_)));
+
+        // Would underflow computing the last of the first half of full rounds.
+        assert!(is_other(constants_from_json(&params(0, 0))));
+        assert!(is_other(constants_from_json(&params(0, 3))));
+        // Full rounds are split evenly around the partial rounds.
+        assert!(is_other(constants_from_json(&params(3, 1))));
+
+        // Would overflow computing the number of round constants.
+        assert!(is_other(constants_from_json(&params(2, usize::MAX))));
+        assert!(is_other(constants_from_json(&params(2, usize::MAX / 2))));
+        assert!(is_other(constants_from_json(&params(usize::MAX - 1, 1))));
+    }
+
     #[test]
     fn serde_hash_pallas() {
         let constants = PoseidonConstants::<S1, U2>::new();
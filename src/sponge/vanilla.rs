@@ -1,6 +1,6 @@
 use crate::hash_type::HashType;
 use crate::poseidon::{Arity, Poseidon, PoseidonConstants};
-use crate::sponge::api::{Hasher, IOPattern, InnerSpongeAPI, SpongeOp};
+use crate::sponge::api::{Hasher, IOPattern, InnerSpongeAPI, SpongeAPI, SpongeOp};
 use crate::{Error, Strength};
 use ff::PrimeField;
 use std::collections::VecDeque;
@@ -497,6 +497,67 @@ impl<F: PrimeField, A: Arity<F>> InnerSpongeAPI<F, A> for Sponge<'_, F, A> {
     }
 }
 
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, A: Arity<F>> zeroize::Zeroize for Sponge<'_, F, A> {
+    /// Overwrite the permutation state and any queued elements with zero.
+    fn zeroize(&mut self) {
+        zeroize::Zeroize::zeroize(&mut self.state);
+        let (front, back) = self.queue.as_mut_slices();
+        crate::zeroize_elements(front);
+        crate::zeroize_elements(back);
+        self.queue.clear();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<F: PrimeField, A: Arity<F>> Drop for Sponge<'_, F, A> {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+/// Hash an arbitrary byte string using the sponge API. `constants` must be sponge constants, as returned by
+/// `SpongeTrait::api_constants`.
+///
+/// A single `0x01` byte is appended to `data`, which is then packed little-endian into field elements of
+/// `F::CAPACITY / 8` bytes each, so that every chunk fits below the modulus. The padding byte ensures inputs
+/// which differ only in trailing zero bytes (including the empty input) pack to different elements.
+pub fn hash_bytes<F: PrimeField, A: Arity<F>>(
+    data: &[u8],
+    constants: &PoseidonConstants<F, A>,
+) -> F {
+    let elements = pack_bytes::<F>(data);
+    let length = elements.len() as u32;
+
+    let mut sponge = Sponge::new_with_constants(constants, Mode::Simplex);
+    let acc = &mut ();
+    let pattern = IOPattern(vec![SpongeOp::Absorb(length), SpongeOp::Squeeze(1)]);
+
+    sponge.start(pattern, None, acc);
+    SpongeAPI::absorb(&mut sponge, length, &elements, acc);
+    let digest = SpongeAPI::squeeze(&mut sponge, 1, acc);
+    sponge.finish(acc).unwrap();
+
+    digest[0]
+}
+
+fn pack_bytes<F: PrimeField>(data: &[u8]) -> Vec<F> {
+    let chunk_size = (F::CAPACITY / 8) as usize;
+    let mut padded = data.to_vec();
+    padded.push(1);
+
+    padded
+        .chunks(chunk_size)
+        .map(|chunk| {
+            // Build the little-endian value arithmetically, as `F::Repr` may not be little-endian.
+            let base = F::from(256);
+            chunk.iter().rev().fold(F::zero(), |acc, byte| {
+                acc * base + F::from(u64::from(*byte))
+            })
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -548,6 +609,41 @@ mod tests {
         assert_eq!(n, result.len());
     }
 
+    #[test]
+    fn test_simplex_streaming() {
+        let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
+
+        // Split points below, at, and beyond the rate.
+        for split in 0..=9 {
+            test_simplex_streaming_aux::<Fr, typenum::U4, _>(&mut rng, 9, split);
+        }
+    }
+
+    fn test_simplex_streaming_aux<F: PrimeField, A: Arity<F>, R: Rng>(
+        rng: &mut R,
+        n: usize,
+        split: usize,
+    ) {
+        let c = Sponge::<F, A>::simplex_constants(n);
+        let acc = &mut ();
+
+        let elements: Vec<F> = (0..n).map(|_| F::random(&mut *rng)).collect();
+
+        let mut single_shot = Sponge::new_with_constants(&c, Mode::Simplex);
+        single_shot.absorb_elements(&elements, acc).unwrap();
+        let expected = single_shot.squeeze(acc).unwrap();
+
+        // Elements arriving in two pieces can be absorbed as they arrive.
+        let mut streaming = Sponge::new_with_constants(&c, Mode::Simplex);
+        let (first, second) = elements.split_at(split);
+        streaming.absorb_elements(first, acc).unwrap();
+        streaming.absorb_elements(second, acc).unwrap();
+        let actual = streaming.squeeze(acc).unwrap();
+
+        assert!(expected.is_some());
+        assert_eq!(expected, actual);
+    }
+
     #[test]
     fn test_duplex_consistency() {
         let mut rng = XorShiftRng::from_seed(crate::TEST_SEED);
@@ -682,6 +778,87 @@ mod tests {
         }
     }
 
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use crate::sponge::api::SpongeAPI;
+        use ff::Field;
+        use zeroize::Zeroize;
+
+        let p = Sponge::<Fr, typenum::U2>::api_constants(Strength::Standard);
+        let mut sponge = Sponge::new_with_constants(&p, Mode::Simplex);
+        let acc = &mut ();
+
+        // Absorb less than the rate, so that the input sits in the state without having been permuted. This is what
+        // `Drop` has to clear if the sponge is abandoned before `finish`.
+        sponge.start(
+            IOPattern(vec![SpongeOp::Absorb(1), SpongeOp::Squeeze(1)]),
+            None,
+            acc,
+        );
+        SpongeAPI::absorb(&mut sponge, 1, &[Fr::from(7)], acc);
+        assert!(sponge.state.elements.contains(&Fr::from(7)));
+        sponge.zeroize();
+        assert!(sponge.state.elements.iter().all(|elt| *elt == Fr::zero()));
+
+        let constants = Sponge::<Fr, typenum::U2>::duplex_constants();
+        let mut sponge = Sponge::new_with_constants(&constants, Mode::Duplex);
+        SpongeTrait::absorb(&mut sponge, &Fr::from(3), &mut ()).unwrap();
+        sponge.enqueue(Fr::from(4));
+        sponge.zeroize();
+        assert!(sponge.state.elements.iter().all(|elt| *elt == Fr::zero()));
+        assert_eq!(None, sponge.dequeue());
+
+        let constants = PoseidonConstants::<Fr, typenum::U2>::new();
+        let mut hasher =
+            Poseidon::<Fr, typenum::U2>::new_with_preimage(&[Fr::from(1), Fr::from(2)], &constants);
+        let mut expected = hasher.clone();
+        assert_eq!(expected.hash(), hasher.hash_zeroizing());
+        assert!(hasher.elements.iter().all(|elt| *elt == Fr::zero()));
+    }
+
+    #[test]
+    fn test_hash_bytes() {
+        let p = Sponge::<Fr, typenum::U4>::api_constants(Strength::Standard);
+
+        let a = hash_bytes(b"neptune", &p);
+        let b = hash_bytes(b"poseidon", &p);
+        assert_eq!(a, hash_bytes(b"neptune", &p));
+        assert_ne!(a, b);
+
+        // Both pack into the same number of field elements.
+        let long_a = [1u8; 40];
+        let mut long_b = long_a;
+        long_b[39] = 2;
+        assert_eq!(
+            pack_bytes::<Fr>(&long_a).len(),
+            pack_bytes::<Fr>(&long_b).len()
+        );
+        assert_ne!(hash_bytes(&long_a, &p), hash_bytes(&long_b, &p));
+
+        // Padding distinguishes the empty input and trailing zeros.
+        let empty = hash_bytes(&[], &p);
+        assert_eq!(empty, hash_bytes(&[], &p));
+        assert_ne!(empty, hash_bytes(&[0], &p));
+        assert_ne!(hash_bytes(&[0], &p), hash_bytes(&[0, 0], &p));
+
+        // Inputs ending exactly on a chunk boundary pad into a new element.
+        let chunk_size = (Fr::CAPACITY / 8) as usize;
+        assert_eq!(2, pack_bytes::<Fr>(&vec![7u8; chunk_size]).len());
+    }
+
+    #[test]
+    fn test_pack_bytes() {
+        assert_eq!(vec![Fr::from(0x01_02_01)], pack_bytes::<Fr>(&[1, 2]));
+
+        // Packing is little-endian, independently of `Fr::Repr`.
+        let chunk_size = (Fr::CAPACITY / 8) as usize;
+        let data: Vec<u8> = (0..chunk_size as u8).map(|i| i.wrapping_mul(37)).collect();
+        let packed = pack_bytes::<Fr>(&data);
+        assert_eq!(scalar_from_bytes_le::<Fr>(&data), Some(packed[0]));
+        assert_eq!(vec![Fr::one()], packed[1..].to_vec());
+    }
+
     #[test]
     #[should_panic]
     fn test_sponge_api_failure() {
@@ -0,0 +1,12 @@
+//! A prime field for tests of the cubic S-box. `x^3` is not a permutation of BLS12-381's or Pasta's scalar fields,
+//! since 3 divides `p - 1` for both, so degree 3 can only be exercised over a field like this one.
+
+use ff::PrimeField;
+
+/// The field of order `p = 2^255 - 46545`. `p` is a safe prime, so `p - 1 = 2q` for a prime `q`, and neither 3 nor 5
+/// divides `p - 1`.
+#[derive(PrimeField)]
+#[PrimeFieldModulus = "57896044618658097711785492504343953926634992332820282019728792003956564773423"]
+#[PrimeFieldGenerator = "5"]
+#[PrimeFieldReprEndianness = "little"]
+pub(crate) struct TestField([u64; 4]);
@@ -227,6 +227,49 @@ where
         tree_height
     }
 
+    /// Replace the leaf at `index` in a tree built with no discarded rows, recomputing only the nodes on its path to
+    /// the root. `base` and `tree` are the base row and the remaining rows, as returned by
+    /// `build_tree(0)`. Returns the new root.
+    pub fn update_leaf(
+        &self,
+        base: &mut [F],
+        tree: &mut [F],
+        index: usize,
+        leaf: F,
+    ) -> Result<F, Error> {
+        if base.len() != self.leaf_count || tree.len() != self.tree_size(0) {
+            return Err(Error::Other(
+                "can only update a complete tree built without discarding rows".to_string(),
+            ));
+        }
+        if index >= self.leaf_count {
+            return Err(Error::IndexOutOfBounds);
+        }
+        let arity = TreeArity::to_usize();
+
+        base[index] = leaf;
+        let mut index = index / arity;
+        let start = index * arity;
+        tree[index] =
+            Poseidon::new_with_preimage(&base[start..start + arity], &self.tree_constants).hash();
+
+        let (mut row_start, mut row_size) = (0, self.leaf_count / arity);
+        while row_size > 1 {
+            let next_row_start = row_start + row_size;
+            let start = row_start + (index / arity) * arity;
+            let hash =
+                Poseidon::new_with_preimage(&tree[start..start + arity], &self.tree_constants)
+                    .hash();
+
+            index /= arity;
+            tree[next_row_start + index] = hash;
+            row_start = next_row_start;
+            row_size /= arity;
+        }
+
+        Ok(tree[tree.len() - 1])
+    }
+
     // Compute root of tree composed of all identical columns. For use in checking correctness of GPU tree-building
     // without the cost of generating a full tree.
     pub fn compute_uniform_tree_root(&mut self, leaf: F) -> Result<F, Error> {
@@ -265,6 +308,39 @@ mod tests {
         test_tree_builder_aux(BatcherType::Gpu, 512, 32, 512);
     }
 
+    #[test]
+    fn test_update_leaf() {
+        let leaves: Vec<Fr> = (0..64).map(|i| Fr::from(i as u64)).collect();
+        let mut builder = TreeBuilder::<Fr, U8>::new(None, leaves.len(), 0).unwrap();
+        let (mut base, mut tree) = builder.add_final_leaves(&leaves).unwrap();
+
+        for (index, value) in [(5, 100), (0, 101), (63, 102)] {
+            let root = builder
+                .update_leaf(&mut base, &mut tree, index, Fr::from(value))
+                .unwrap();
+
+            let mut expected_leaves = base.clone();
+            expected_leaves[index] = Fr::from(value);
+            let (expected_base, expected_tree) =
+                builder.add_final_leaves(&expected_leaves).unwrap();
+
+            assert_eq!(expected_base, base);
+            assert_eq!(expected_tree, tree);
+            assert_eq!(expected_tree[expected_tree.len() - 1], root);
+        }
+
+        assert!(matches!(
+            builder.update_leaf(&mut base, &mut tree, 64, Fr::zero()),
+            Err(Error::IndexOutOfBounds)
+        ));
+
+        let mut discarding = TreeBuilder::<Fr, U8>::new(None, leaves.len(), 1).unwrap();
+        let (mut base, mut tree) = discarding.add_final_leaves(&leaves).unwrap();
+        assert!(discarding
+            .update_leaf(&mut base, &mut tree, 0, Fr::zero())
+            .is_err());
+    }
+
     fn test_tree_builder_aux(
         batcher_type: BatcherType,
         leaves: usize,
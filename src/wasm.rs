@@ -0,0 +1,53 @@
+//! A string-based entry point for hashing from JavaScript, e.g. through `wasm-bindgen`, where passing field
+//! elements around as hex is simpler than exposing the field types.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use blstrs::Scalar as Fr;
+use generic_array::typenum::U2;
+use once_cell::sync::Lazy;
+
+use crate::matrix::{format_element, parse_element};
+use crate::poseidon::{Poseidon, PoseidonConstants};
+
+/// The default (arity 2, Merkle tree) constants, generated on first use.
+static CONSTANTS: Lazy<PoseidonConstants<Fr, U2>> = Lazy::new(PoseidonConstants::new);
+
+/// Hash the two BLS12-381 scalars given as big-endian hex (with or without a `0x` prefix) with the default arity 2
+/// constants, and return the digest in the same format. Invalid input yields an error message instead.
+pub fn hash_hex(inputs_hex: &[String]) -> String {
+    let inputs = match inputs_hex
+        .iter()
+        .map(|hex| parse_element::<Fr>(hex).ok_or(hex))
+        .collect::<Result<Vec<_>, _>>()
+    {
+        Ok(inputs) => inputs,
+        Err(hex) => return format!("Invalid field element: {:?}.", hex),
+    };
+
+    match Poseidon::try_new_with_preimage(&inputs, &CONSTANTS) {
+        Ok(mut p) => format_element(&p.hash()),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poseidon::hash2;
+
+    #[test]
+    fn test_hash_hex() {
+        let a = Fr::from(1);
+        let b = Fr::from(0x1234_5678);
+        let expected = hash2(a, b, &CONSTANTS);
+
+        let digest = hash_hex(&["0x1".to_string(), "12345678".to_string()]);
+        assert_eq!(format_element(&expected), digest);
+        assert_eq!(Some(expected), parse_element(&digest));
+
+        assert!(hash_hex(&["0xnot hex".to_string(), "0x2".to_string()])
+            .starts_with("Invalid field element"));
+        assert!(hash_hex(&["0x1".to_string()]).starts_with("Invalid preimage size"));
+    }
+}
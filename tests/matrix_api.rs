@@ -0,0 +1,76 @@
+use blstrs::Scalar as Fr;
+use ff::{Field, PrimeField};
+use neptune::matrix::{
+    apply_matrix, apply_matrix_validated, check_affine_commute, determinant_via_elimination,
+    inverse_and_transpose, make_identity, scale_matrix, scale_vec, solve_linear_system, transpose,
+    try_apply_matrix, validate_matrix, Mat, Matrix,
+};
+use neptune::mds::{circulant, equivalent_mds, is_mds, mds_default};
+use neptune::MatrixError;
+
+fn small_matrix() -> Matrix<Fr> {
+    vec![
+        vec![Fr::from(2), Fr::from(1), Fr::from(1)],
+        vec![Fr::from(1), Fr::from(3), Fr::from(2)],
+        vec![Fr::from(1), Fr::from(0), Fr::from(0)],
+    ]
+}
+
+#[test]
+fn matrix_helpers_are_public() {
+    let m = small_matrix();
+    let v = vec![Fr::from(1), Fr::from(2), Fr::from(3)];
+
+    assert!(validate_matrix(&m));
+    assert!(!validate_matrix(&vec![vec![Fr::one()], vec![]]));
+    assert_eq!(-Fr::one(), determinant_via_elimination(&m));
+
+    let (m_inv, m_inv_t) = inverse_and_transpose(&m).unwrap();
+    assert_eq!(transpose(&m_inv), m_inv_t);
+    assert!(check_affine_commute(
+        &m,
+        &m_inv,
+        &v,
+        &scale_vec(Fr::from(5), &v)
+    ));
+
+    // x·M = v
+    let x = solve_linear_system(&transpose(&m), &v).unwrap();
+    assert_eq!(v, apply_matrix(&m, &x));
+
+    assert_eq!(Ok(apply_matrix(&m, &v)), try_apply_matrix(&m, &v));
+    assert_eq!(
+        Err(MatrixError::NotSquare),
+        try_apply_matrix(&m[1..].to_vec(), &v)
+    );
+
+    let reprs: Matrix<_> = m
+        .iter()
+        .map(|row| row.iter().map(PrimeField::to_repr).collect())
+        .collect();
+    let v_reprs: Vec<_> = v.iter().map(PrimeField::to_repr).collect();
+    assert_eq!(
+        Ok(apply_matrix(&m, &v)),
+        apply_matrix_validated::<Fr>(&reprs, &v_reprs)
+    );
+
+    assert_eq!(
+        make_identity::<Fr>(3),
+        scale_matrix(Fr::one(), &make_identity(3))
+    );
+    assert!(format!("{:?}", Mat(m)).starts_with("3x3 matrix"));
+}
+
+#[test]
+fn mds_helpers_are_public() {
+    let m = mds_default::<Fr>(3);
+    assert!(is_mds(&m));
+    assert!(!is_mds(&circulant(&[Fr::one(), Fr::one(), Fr::one()])));
+
+    let basis = small_matrix();
+    let equivalent = equivalent_mds(&m, &basis).unwrap();
+    assert_eq!(
+        determinant_via_elimination(&m),
+        determinant_via_elimination(&equivalent)
+    );
+}